@@ -0,0 +1,115 @@
+//! 距離テーブル前計算の効果を測るベンチマーク。
+//!
+//! `cargo bench --bench distance_table` で実行します。
+//! ヒルクライムの近傍走査と同じ形（全座席ペア × 相手数）で距離を引き、
+//! `DistanceFn` を毎回呼ぶ場合と、`Problem::distance`（前計算テーブル）を比較します。
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sekigae3::{DefaultDistanceFn, DistanceFn, EuclideanDistanceFn, ILSA, Problem, Seat};
+
+fn classroom(seat_count: usize, cols: usize) -> Vec<Seat> {
+    (0..seat_count)
+        .map(|i| Seat {
+            x: (i % cols) as i16,
+            y: (i / cols) as i16,
+        })
+        .collect()
+}
+
+fn build_problem<D: DistanceFn>(seat_count: usize, distance_fn: D) -> Problem<D> {
+    let seats = classroom(seat_count, 8);
+    let want_seats = (0..seat_count)
+        .map(|s| {
+            vec![
+                (((s * 7) % seat_count) as u16, 1.0),
+                (((s * 3) % seat_count) as u16, 0.5),
+            ]
+        })
+        .collect();
+    let pair_edges = (0..seat_count)
+        .map(|s| {
+            (1..=4)
+                .map(|k| (((s + k * 5) % seat_count) as u16, 0.8))
+                .collect()
+        })
+        .collect();
+    Problem::with_distance_fn(seats, want_seats, pair_edges, distance_fn)
+}
+
+/// ヒルクライム近傍走査の模擬：全ペア (i, j) × 相手 4 人の距離を引く
+fn neighbourhood_scan(n: usize, dist: impl Fn(u16, u16) -> u16) -> u64 {
+    let mut acc = 0u64;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            for k in 1..=4 {
+                let other = ((i + k * 5) % n) as u16;
+                acc += dist(j as u16, other) as u64 + dist(i as u16, other) as u64;
+            }
+        }
+    }
+    acc
+}
+
+fn time_per_iter(mut f: impl FnMut()) -> Duration {
+    // ウォームアップ
+    for _ in 0..3 {
+        f();
+    }
+    let mut iters = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500) {
+        f();
+        iters += 1;
+    }
+    start.elapsed() / iters
+}
+
+fn bench_scan<D: DistanceFn>(label: &str, seat_count: usize, distance_fn: D) {
+    let problem = build_problem(seat_count, distance_fn);
+    let seats = problem.seats();
+
+    let direct = time_per_iter(|| {
+        black_box(neighbourhood_scan(seat_count, |a, b| {
+            let sa = seats[a as usize];
+            let sb = seats[b as usize];
+            problem.distance_fn().distance((sa.x, sa.y), (sb.x, sb.y))
+        }));
+    });
+    let table = time_per_iter(|| {
+        black_box(neighbourhood_scan(seat_count, |a, b| {
+            problem.distance(a, b)
+        }));
+    });
+
+    println!(
+        "scan {:>9} seats={:>3}: distance_fn {:>10.1?}  table {:>10.1?}  speedup x{:.2}",
+        label,
+        seat_count,
+        direct,
+        table,
+        direct.as_secs_f64() / table.as_secs_f64()
+    );
+}
+
+fn bench_solve(seat_count: usize, budget: usize) {
+    let problem = build_problem(seat_count, DefaultDistanceFn);
+    let elapsed = time_per_iter(|| {
+        let mut ilsa = ILSA::new(&problem, 42);
+        black_box(ilsa.solve(budget).expect("feasible problem"));
+    });
+    println!(
+        "solve seats={:>3} budget={:>3}: {:>10.1?} per solve",
+        seat_count, budget, elapsed
+    );
+}
+
+fn main() {
+    for seat_count in [40, 200] {
+        bench_scan("manhattan", seat_count, DefaultDistanceFn);
+        bench_scan("euclid", seat_count, EuclideanDistanceFn);
+    }
+    bench_solve(40, 40);
+    bench_solve(200, 4);
+}
@@ -0,0 +1,204 @@
+use log::info;
+
+use crate::{DistanceFn, engine::problem::DefaultDistanceFn};
+
+use super::{
+    error::SolveError,
+    individual::Individual,
+    objective::Objective,
+    problem::{EMPTY_SEAT, Problem},
+};
+
+/// 希望席コストだけの問題（線形割当問題）の厳密ソルバー。
+///
+/// ペア距離と分離条件を持たない問題は、学生 × 座席の線形割当問題になります
+/// （安定化項は移動人数の上限がなければ、履歴は過去の座席のペナルティだけなら扱えます）。
+/// ハンガリアン法で O(n³) で解くため、返す解は最適です。
+/// 固定割り当てと禁止席は制約として扱います。目的関数は `Objective::Sum` のみに対応します。
+pub struct AssignmentSolver<'p, D: DistanceFn = DefaultDistanceFn> {
+    problem: &'p Problem<D>,
+}
+
+impl<'p, D: DistanceFn> AssignmentSolver<'p, D> {
+    /// 新規インスタンスを作成します。
+    pub fn new(problem: &'p Problem<D>) -> Self {
+        Self { problem }
+    }
+
+    /// 最適解を返します。
+    ///
+    /// 重みが 0 でないペア、分離条件、移動人数の上限、過去の隣のペナルティがある場合、コスト項を変更した場合は
+    /// `SolveError::NotAssignmentProblem` を、目的関数が `Sum` でない場合は
+    /// `SolveError::UnsupportedObjective` を返します。
+    pub fn solve(&self) -> Result<Individual, SolveError> {
+        self.problem.validate()?;
+        if self.problem.objective != Objective::Sum {
+            return Err(SolveError::UnsupportedObjective);
+        }
+        if !is_linear(self.problem) {
+            return Err(SolveError::NotAssignmentProblem);
+        }
+        let best = Individual::from_assignment(self.problem, solve_linear(self.problem)?);
+        info!("assignment solver done: cost={:.3}", best.cost());
+        Ok(best)
+    }
+}
+
+/// ペア距離・分離条件・移動人数上限・過去の隣を持たず、既定のコスト項のもとで
+/// 学生ごとに独立なコストだけで評価が決まるかを返す
+fn is_linear<D: DistanceFn>(problem: &Problem<D>) -> bool {
+    problem.separations.is_empty()
+        && problem.default_cost_terms
+        && problem.max_moves.is_none()
+        && problem.history_pairs.is_empty()
+        && problem
+            .pair_graph
+            .iter()
+            .all(|edges| edges.iter().all(|&(_, w)| w == 0.0))
+}
+
+/// 学生ごとに独立なコスト（希望席 + 過去の座席 + 移動の重み）だけを見た最適割り当て（`by_seat`）を求める
+///
+/// ペア距離・分離条件・移動人数上限・過去の隣は無視し、固定割り当てと禁止席は守ります。
+/// `problem` は検証済みであることを前提とします。
+pub(crate) fn solve_linear<D: DistanceFn>(problem: &Problem<D>) -> Result<Vec<u16>, SolveError> {
+    if let Some(&(student, seat)) = problem
+        .pinned
+        .iter()
+        .find(|&&(student, seat)| problem.is_forbidden(student, seat))
+    {
+        return Err(SolveError::PinnedToForbiddenSeat { student, seat });
+    }
+
+    let mut by_seat = vec![EMPTY_SEAT; problem.seat_count()];
+    let mut pinned_student = vec![false; problem.student_count()];
+    for &(student, seat) in &problem.pinned {
+        by_seat[seat as usize] = student;
+        pinned_student[student as usize] = true;
+    }
+
+    // 固定されていない学生 × 固定されていない座席の割当問題
+    let students: Vec<u16> = (0..problem.student_count() as u16)
+        .filter(|&student| !pinned_student[student as usize])
+        .collect();
+    let seats = &problem.movable_seats;
+    if students.is_empty() {
+        return Ok(by_seat);
+    }
+
+    let mut cost = vec![0.0f64; students.len() * seats.len()];
+    let mut allowed = vec![true; students.len() * seats.len()];
+    for (row, &student) in students.iter().enumerate() {
+        for (col, &seat) in seats.iter().enumerate() {
+            cost[row * seats.len() + col] = problem.linear_cost(student, seat) as f64;
+            allowed[row * seats.len() + col] = !problem.is_forbidden(student, seat);
+        }
+    }
+
+    let col_of_row =
+        hungarian_masked(&mut cost, &allowed, students.len(), seats.len()).map_err(|row| {
+            SolveError::NoFeasibleAssignment {
+                student: students[row],
+            }
+        })?;
+    for (row, col) in col_of_row.into_iter().enumerate() {
+        by_seat[seats[col] as usize] = students[row];
+    }
+    Ok(by_seat)
+}
+
+/// 許可されていない組 (`allowed[row * cols + col] == false`) を使わずに最小コスト割当を求める
+///
+/// 割り当てられない行があれば、その行番号を `Err` で返します。`cost` の禁止要素は上書きされます。
+pub(crate) fn hungarian_masked(
+    cost: &mut [f64],
+    allowed: &[bool],
+    rows: usize,
+    cols: usize,
+) -> Result<Vec<usize>, usize> {
+    // 禁止要素は「どの許可された割り当てより高い」有限コストで表し、解に残ったら実行不能とする
+    if allowed.iter().any(|&ok| !ok) {
+        let max_cost = cost.iter().fold(0.0f64, |acc, c| acc.max(c.abs()));
+        let forbidden_cost = (max_cost + 1.0) * (rows as f64 + 1.0) * 2.0;
+        for (c, &ok) in cost.iter_mut().zip(allowed) {
+            if !ok {
+                *c = forbidden_cost;
+            }
+        }
+    }
+    let col_of_row = hungarian(cost, rows, cols);
+    match (0..rows).find(|&row| !allowed[row * cols + col_of_row[row]]) {
+        Some(row) => Err(row),
+        None => Ok(col_of_row),
+    }
+}
+
+/// ハンガリアン法（ポテンシャル付き最短増加路）で `rows × cols` の最小コスト割当を求める
+///
+/// `rows <= cols` を前提とし、各行に割り当てた列を返します。
+fn hungarian(cost: &[f64], rows: usize, cols: usize) -> Vec<usize> {
+    debug_assert!(rows <= cols);
+    // 1-indexed（0 番は番兵）
+    let mut u = vec![0.0f64; rows + 1];
+    let mut v = vec![0.0f64; cols + 1];
+    let mut row_of_col = vec![0usize; cols + 1];
+    let mut way = vec![0usize; cols + 1];
+    let mut min_v = vec![0.0f64; cols + 1];
+    let mut used = vec![false; cols + 1];
+
+    for row in 1..=rows {
+        row_of_col[0] = row;
+        let mut col0 = 0usize;
+        min_v.fill(f64::INFINITY);
+        used.fill(false);
+        loop {
+            used[col0] = true;
+            let row0 = row_of_col[col0];
+            let mut delta = f64::INFINITY;
+            let mut col1 = 0usize;
+            for col in 1..=cols {
+                if used[col] {
+                    continue;
+                }
+                let reduced = cost[(row0 - 1) * cols + (col - 1)] - u[row0] - v[col];
+                if reduced < min_v[col] {
+                    min_v[col] = reduced;
+                    way[col] = col0;
+                }
+                if min_v[col] < delta {
+                    delta = min_v[col];
+                    col1 = col;
+                }
+            }
+            for col in 0..=cols {
+                if used[col] {
+                    u[row_of_col[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_v[col] -= delta;
+                }
+            }
+            col0 = col1;
+            if row_of_col[col0] == 0 {
+                break;
+            }
+        }
+        // 増加路に沿って割り当てを付け替える
+        loop {
+            let col1 = way[col0];
+            row_of_col[col0] = row_of_col[col1];
+            col0 = col1;
+            if col0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut col_of_row = vec![0usize; rows];
+    for col in 1..=cols {
+        if row_of_col[col] != 0 {
+            col_of_row[row_of_col[col] - 1] = col - 1;
+        }
+    }
+    col_of_row
+}
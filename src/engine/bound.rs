@@ -0,0 +1,146 @@
+use crate::DistanceFn;
+
+use super::{
+    assignment,
+    error::SolveError,
+    objective::Objective,
+    problem::{EMPTY_SEAT, Problem},
+};
+
+/// 解のコストと下界の差（最適性ギャップ）。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapReport {
+    /// 解のコスト
+    pub cost: f32,
+    /// 最適コストの下界
+    pub lower_bound: f32,
+    /// `cost - lower_bound`（0 以上）
+    pub absolute_gap: f32,
+    /// `absolute_gap / |cost|`（両方 0 なら 0、`cost` だけ 0 なら無限大）
+    pub relative_gap: f32,
+}
+
+impl GapReport {
+    /// コストと下界からギャップを計算します。
+    pub fn new(cost: f32, lower_bound: f32) -> Self {
+        let absolute_gap = (cost - lower_bound).max(0.0);
+        let relative_gap = if absolute_gap == 0.0 {
+            0.0
+        } else if cost == 0.0 {
+            f32::INFINITY
+        } else {
+            absolute_gap / cost.abs()
+        };
+        Self {
+            cost,
+            lower_bound,
+            absolute_gap,
+            relative_gap,
+        }
+    }
+}
+
+impl<D: DistanceFn> Problem<D> {
+    /// 最適コストの下界を計算します。
+    ///
+    /// コスト項が既定のままなら、希望席・過去の座席・移動の重みの項は割当問題（固定割り当て・禁止席込み）の
+    /// 最適値、ペアの項は各ペアが取り得る最小距離（負の重みは最大距離）で見積もります。
+    /// コスト項を変更した問題では、各項の `CostTerm::lower_bound` × 重みを足し、
+    /// 過去の座席と移動の重みは 0 とみなします（1 つでも `None` の項があれば
+    /// `SolveError::UnsupportedCostTerm`）。
+    /// 過去の隣のペナルティと絶対条件の違反ペナルティは 0 とみなします。
+    ///
+    /// 目的関数が `MinMax` / `Weighted` のときは、最大の学生コストが平均以上であることから
+    /// 学生コストの合計の下界を学生数で割った値を最大値の下界に使います。
+    /// `LexMinMax` には対応していません (`SolveError::UnsupportedObjective`)。
+    pub fn lower_bound(&self) -> Result<f32, SolveError> {
+        self.validate()?;
+        let (students, shared) = if self.default_cost_terms {
+            (self.sum_lower_bound()?, 0.0)
+        } else {
+            // 学生に帰属する項とそうでない項に分ける（前者だけが目的関数の学生コストに入る）
+            let mut students = 0.0f32;
+            let mut shared = 0.0f32;
+            for (weight, term) in &self.cost_terms {
+                let bound = weight
+                    * term
+                        .lower_bound(self)
+                        .ok_or(SolveError::UnsupportedCostTerm)?;
+                if term.per_student() {
+                    students += bound;
+                } else {
+                    shared += bound;
+                }
+            }
+            (students, shared)
+        };
+        Ok(self.objective_lower_bound(students)? + shared)
+    }
+
+    /// 学生コストの合計の下界 `students` から、目的関数の下界を求める
+    fn objective_lower_bound(&self, students: f32) -> Result<f32, SolveError> {
+        let n = self.student_count().max(1) as f32;
+        match self.objective {
+            Objective::Sum => Ok(students),
+            Objective::MinMax => Ok(students / n),
+            Objective::Weighted {
+                sum_weight,
+                max_weight,
+            } => Ok(sum_weight * students + max_weight * students / n),
+            Objective::LexMinMax { .. } => Err(SolveError::UnsupportedObjective),
+        }
+    }
+
+    /// 学生コストの合計の下界（コスト項が既定のままの場合）
+    fn sum_lower_bound(&self) -> Result<f32, SolveError> {
+        let by_seat = assignment::solve_linear(self)?;
+        let mut bound = 0.0f32;
+        for (seat, &student) in by_seat.iter().enumerate() {
+            if student != EMPTY_SEAT {
+                bound += self.linear_cost(student, seat as u16);
+            }
+        }
+
+        let mut pinned_seat = vec![EMPTY_SEAT; self.student_count()];
+        for &(student, seat) in &self.pinned {
+            pinned_seat[student as usize] = seat;
+        }
+        let seats: Vec<u16> = (0..self.seat_count() as u16).collect();
+        let (min_distance, max_distance) = distance_range(self, &seats);
+        for (a, edges) in self.pair_graph.iter().enumerate() {
+            for &(b, w) in edges {
+                if a as u16 >= b {
+                    continue;
+                }
+                let (seat_a, seat_b) = (pinned_seat[a], pinned_seat[b as usize]);
+                let d = if seat_a != EMPTY_SEAT && seat_b != EMPTY_SEAT {
+                    self.distance(seat_a, seat_b) as f32
+                } else if w >= 0.0 {
+                    min_distance
+                } else {
+                    max_distance
+                };
+                bound += w * d;
+            }
+        }
+        Ok(bound)
+    }
+}
+
+/// 座席集合 `seats` の中での座席間距離の最小値と最大値
+pub(crate) fn distance_range<D: DistanceFn>(problem: &Problem<D>, seats: &[u16]) -> (f32, f32) {
+    let mut min_distance = f32::INFINITY;
+    let mut max_distance = 0.0f32;
+    for (k, &a) in seats.iter().enumerate() {
+        for &b in &seats[(k + 1)..] {
+            let d = problem.distance(a, b) as f32;
+            min_distance = min_distance.min(d);
+            max_distance = max_distance.max(d);
+        }
+    }
+    if min_distance.is_finite() {
+        (min_distance, max_distance)
+    } else {
+        (0.0, 0.0)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::DistanceFn;
+
+use super::{individual::Individual, problem::Problem};
+
+/// 個体のコストを学生ごとに分解した内訳。
+///
+/// `students` の `total` を学生 ID 順に足し、`move_limit_penalty` と `cost_terms` を加えた値は、
+/// 目的関数が `Objective::Sum` のとき `Individual::cost()` と（浮動小数点の丸めまで含めて）一致します。
+/// `Sum` 以外の目的関数では内訳の合計は `cost()` と一致しないため、`objective` を参照してください。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostBreakdown {
+    /// 学生ごとの内訳（index = StudentId）
+    pub students: Vec<StudentCost>,
+    /// 移動人数上限を超えた分のペナルティ（特定の学生には帰属しない）
+    pub move_limit_penalty: f32,
+    /// 学生に帰属しないコスト項（`CostTerm::per_student` が `false`）ごとの重み付きコスト（登録順）
+    pub cost_terms: Vec<f32>,
+    /// 問題の目的関数で評価した総コスト（`Individual::cost()` と同じ値）
+    pub objective: f32,
+}
+
+impl CostBreakdown {
+    /// 内訳の合計を返します。
+    ///
+    /// 目的関数が `Sum` なら `Individual::cost()` と一致します。それ以外では学生コストの合計で、
+    /// 目的関数の値 (`objective`) とは異なります。
+    pub fn total(&self) -> f32 {
+        let mut cost = 0.0f32;
+        for student in &self.students {
+            cost += student.total;
+        }
+        let mut terms = 0.0f32;
+        for &term in &self.cost_terms {
+            terms += term;
+        }
+        cost + self.move_limit_penalty + terms
+    }
+}
+
+/// 学生 1 人分のコストの内訳。
+///
+/// `best_want` / `preference` / `pairs` は組み込みの希望席・ペア距離の項を重み 1 で評価した説明用の値で、
+/// `total` には登録されているコスト項の重み付きの負担分 (`cost_terms`) が含まれます。
+/// ペア距離・過去の隣・分離条件のコストは 2 人で半分ずつ負担します。
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudentCost {
+    pub student: u16,
+    pub seat: u16,
+    /// 希望席コストを決めた `want_seats` の候補（候補がなければ `None`）
+    pub best_want: Option<WantContribution>,
+    /// 希望席コスト
+    pub preference: f32,
+    /// ペアごとの距離と負担分（相手 ID 昇順）
+    pub pairs: Vec<PairContribution>,
+    /// 過去と同じ座席に座ったペナルティ
+    pub history_seat: f32,
+    /// 過去と同じ隣になったペナルティの負担分
+    pub history_neighbours: f32,
+    /// 違反している分離条件のペナルティの負担分
+    pub separation: f32,
+    /// 基準配置から移動したコスト
+    pub movement: f32,
+    /// 学生ごとに分けられるコスト項（既定では希望席とペア距離）の重み付き負担分の合計
+    pub cost_terms: f32,
+    /// この学生に帰属するコストの合計
+    pub total: f32,
+}
+
+/// `want_seats` の候補 1 件の評価。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WantContribution {
+    /// 希望した座席
+    pub seat: u16,
+    /// 実際の座席との距離
+    pub distance: u16,
+    pub weight: f32,
+    /// `distance × weight`
+    pub cost: f32,
+}
+
+/// ペア 1 組の評価。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairContribution {
+    pub other: u16,
+    /// 2 人の座席間距離
+    pub distance: u16,
+    /// 正規化後 (`pair_graph`) の重み
+    pub weight: f32,
+    /// ペア全体のコスト `distance × weight`
+    pub cost: f32,
+    /// この学生の負担分（`cost` の半分）
+    pub share: f32,
+}
+
+impl Individual {
+    /// コストを学生ごとに分解した内訳を返します。
+    ///
+    /// 「なぜこの席なのか」を説明するためのもので、希望席のどれが一番近かったか、
+    /// 各ペアとの距離などを含みます。
+    pub fn breakdown<D: DistanceFn>(&self, problem: &Problem<D>) -> CostBreakdown {
+        let seat_of = &self.seat_of;
+        let students = (0..seat_of.len() as u16)
+            .map(|student| student_breakdown(problem, seat_of, student))
+            .collect();
+        CostBreakdown {
+            students,
+            move_limit_penalty: Self::move_limit_penalty(problem, seat_of),
+            cost_terms: problem
+                .cost_terms
+                .iter()
+                .filter(|(_, term)| !term.per_student())
+                .map(|(weight, term)| weight * term.evaluate(problem, seat_of))
+                .collect(),
+            objective: Self::calc_cost(problem, seat_of),
+        }
+    }
+}
+
+fn student_breakdown<D: DistanceFn>(
+    problem: &Problem<D>,
+    seat_of: &[u16],
+    student: u16,
+) -> StudentCost {
+    let s = student as usize;
+    let seat = seat_of[s];
+
+    let best_want = problem.want_seats[s]
+        .iter()
+        .map(|&(want, weight)| {
+            let distance = problem.distance(seat, want);
+            WantContribution {
+                seat: want,
+                distance,
+                weight,
+                cost: distance as f32 * weight,
+            }
+        })
+        .min_by(|a, b| a.cost.total_cmp(&b.cost));
+
+    let pairs = problem.pair_graph[s]
+        .iter()
+        .map(|&(other, weight)| {
+            let distance = problem.distance(seat, seat_of[other as usize]);
+            PairContribution {
+                other,
+                distance,
+                weight,
+                cost: weight * distance as f32,
+                share: 0.5 * weight * distance as f32,
+            }
+        })
+        .collect();
+
+    let history_neighbours = problem.history_pairs.get(s).map_or(0.0, |edges| {
+        edges
+            .iter()
+            .filter(|&&(other, _)| problem.are_neighbours(seat, seat_of[other as usize]))
+            .map(|&(_, penalty)| 0.5 * penalty)
+            .sum()
+    });
+    let separation = problem.separations.get(s).map_or(0.0, |rules| {
+        rules
+            .iter()
+            .filter(|&&(other, rule)| {
+                problem.separation_violated(rule, seat, seat_of[other as usize])
+            })
+            .map(|_| 0.5 * problem.separation_penalty)
+            .sum()
+    });
+
+    StudentCost {
+        student,
+        seat,
+        best_want,
+        preference: problem.preference_cost(student, seat),
+        pairs,
+        history_seat: problem.history_seat_cost(student, seat),
+        history_neighbours,
+        separation,
+        movement: if problem.has_moved(student, seat) {
+            problem.move_weight
+        } else {
+            0.0
+        },
+        cost_terms: problem
+            .cost_terms
+            .iter()
+            .filter(|(_, term)| term.per_student())
+            .map(|(weight, term)| {
+                weight * term.student_cost(problem, student, &|other| seat_of[other as usize])
+            })
+            .sum(),
+        total: Individual::student_cost(problem, seat_of, student),
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use crate::DistanceFn;
+
+use super::{
+    error::ProblemError,
+    problem::{
+        DefaultDistanceFn, EMPTY_SEAT, PairMergePolicy, Problem, Seat, Separation, WeightedSeatPref,
+    },
+};
+
+/// 学生名・座席ラベルをキーにして `Problem` を組み立てるビルダー。
+///
+/// 学生と座席を追加した順に StudentId / SeatId が割り当てられます。
+/// 希望座席やペア関係はキーで指定し、未登録のキーは `build` 時にエラーになります。
+///
+/// ## Example
+/// ```rust
+/// use sekigae3::{ILSA, ProblemBuilder, Seat};
+///
+/// let mut builder = ProblemBuilder::new();
+/// builder.seat("A-1", Seat { x: 0, y: 0 }).seat("A-2", Seat { x: 1, y: 0 });
+/// builder.student("sato").student("suzuki");
+/// builder.want_seat("sato", "A-2", 1.0).pair("sato", "suzuki", 0.5);
+///
+/// let (problem, ids) = builder.build().expect("valid keys");
+/// let best = ILSA::new(&problem, 42).solve(4).expect("feasible problem");
+///
+/// let seating = ids.assignments(best.by_seat());
+/// assert!(seating.contains(&("sato", "A-2")));
+/// ```
+pub struct ProblemBuilder<D: DistanceFn = DefaultDistanceFn> {
+    distance_fn: D,
+    students: Vec<String>,
+    seats: Vec<(String, Seat)>,
+    want_seats: Vec<(String, String, f32)>,
+    pairs: Vec<(String, String, f32)>,
+    pins: Vec<(String, String)>,
+    forbidden: Vec<(String, String)>,
+    separations: Vec<(String, String, Separation)>,
+    separation_penalty: Option<f32>,
+    pair_merge_policy: PairMergePolicy,
+}
+
+impl ProblemBuilder<DefaultDistanceFn> {
+    /// マンハッタン距離を使うビルダーを作成します。
+    pub fn new() -> Self {
+        Self::with_distance_fn(DefaultDistanceFn)
+    }
+}
+
+impl Default for ProblemBuilder<DefaultDistanceFn> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: DistanceFn> ProblemBuilder<D> {
+    /// 距離関数を指定してビルダーを作成します。
+    pub fn with_distance_fn(distance_fn: D) -> Self {
+        Self {
+            distance_fn,
+            students: Vec::new(),
+            seats: Vec::new(),
+            want_seats: Vec::new(),
+            pairs: Vec::new(),
+            pins: Vec::new(),
+            forbidden: Vec::new(),
+            separations: Vec::new(),
+            separation_penalty: None,
+            pair_merge_policy: PairMergePolicy::default(),
+        }
+    }
+
+    /// 学生を追加します。
+    pub fn student(&mut self, key: impl Into<String>) -> &mut Self {
+        self.students.push(key.into());
+        self
+    }
+
+    /// 座席をラベル付きで追加します。
+    pub fn seat(&mut self, key: impl Into<String>, seat: Seat) -> &mut Self {
+        self.seats.push((key.into(), seat));
+        self
+    }
+
+    /// 学生の希望座席を追加します。重みの意味は `Problem::want_seats` と同じです。
+    pub fn want_seat(
+        &mut self,
+        student: impl Into<String>,
+        seat: impl Into<String>,
+        weight: f32,
+    ) -> &mut Self {
+        self.want_seats.push((student.into(), seat.into(), weight));
+        self
+    }
+
+    /// 学生 `from` から `to` へのペア関係を追加します。重みの意味は `Problem::pair_edges` と同じです。
+    pub fn pair(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        weight: f32,
+    ) -> &mut Self {
+        self.pairs.push((from.into(), to.into(), weight));
+        self
+    }
+
+    /// 学生を座席に固定します。
+    pub fn pin(&mut self, student: impl Into<String>, seat: impl Into<String>) -> &mut Self {
+        self.pins.push((student.into(), seat.into()));
+        self
+    }
+
+    /// 学生の禁止席を追加します。
+    pub fn forbid(&mut self, student: impl Into<String>, seat: impl Into<String>) -> &mut Self {
+        self.forbidden.push((student.into(), seat.into()));
+        self
+    }
+
+    /// 学生ペアの分離条件を追加します。
+    pub fn separate(
+        &mut self,
+        a: impl Into<String>,
+        b: impl Into<String>,
+        rule: Separation,
+    ) -> &mut Self {
+        self.separations.push((a.into(), b.into(), rule));
+        self
+    }
+
+    /// 分離条件 1 件の違反あたりのペナルティを設定します。
+    pub fn separation_penalty(&mut self, penalty: f32) -> &mut Self {
+        self.separation_penalty = Some(penalty);
+        self
+    }
+
+    /// 非対称なペア回答の統合方法を設定します。
+    pub fn pair_merge_policy(&mut self, policy: PairMergePolicy) -> &mut Self {
+        self.pair_merge_policy = policy;
+        self
+    }
+
+    /// キーを ID に解決して `Problem` と ID 対応表を構築します。
+    pub fn build(self) -> Result<(Problem<D>, IdMap), ProblemError> {
+        let mut ids = IdMap {
+            students: Vec::with_capacity(self.students.len()),
+            student_ids: HashMap::with_capacity(self.students.len()),
+            seats: Vec::with_capacity(self.seats.len()),
+            seat_ids: HashMap::with_capacity(self.seats.len()),
+        };
+        for key in self.students {
+            if ids.student_ids.contains_key(&key) {
+                return Err(ProblemError::DuplicateStudentKey { key });
+            }
+            let id =
+                u16::try_from(ids.students.len()).map_err(|_| ProblemError::TooManyStudents {
+                    count: ids.students.len() + 1,
+                })?;
+            ids.student_ids.insert(key.clone(), id);
+            ids.students.push(key);
+        }
+        let mut seats = Vec::with_capacity(self.seats.len());
+        for (key, seat) in self.seats {
+            if ids.seat_ids.contains_key(&key) {
+                return Err(ProblemError::DuplicateSeatKey { key });
+            }
+            let id = u16::try_from(ids.seats.len()).map_err(|_| ProblemError::TooManySeats {
+                count: ids.seats.len() + 1,
+            })?;
+            ids.seat_ids.insert(key.clone(), id);
+            ids.seats.push(key);
+            seats.push(seat);
+        }
+
+        let mut want_seats: Vec<Vec<WeightedSeatPref>> = vec![Vec::new(); ids.students.len()];
+        for (student, seat, weight) in &self.want_seats {
+            let student = ids.resolve_student(student)?;
+            let seat = ids.resolve_seat(seat)?;
+            want_seats[student as usize].push((seat, *weight));
+        }
+        let mut pair_edges: Vec<Vec<WeightedSeatPref>> = vec![Vec::new(); ids.students.len()];
+        for (from, to, weight) in &self.pairs {
+            let from = ids.resolve_student(from)?;
+            let to = ids.resolve_student(to)?;
+            pair_edges[from as usize].push((to, *weight));
+        }
+        let pins = self
+            .pins
+            .iter()
+            .map(|(student, seat)| Ok((ids.resolve_student(student)?, ids.resolve_seat(seat)?)))
+            .collect::<Result<Vec<_>, ProblemError>>()?;
+        let forbidden = self
+            .forbidden
+            .iter()
+            .map(|(student, seat)| Ok((ids.resolve_student(student)?, ids.resolve_seat(seat)?)))
+            .collect::<Result<Vec<_>, ProblemError>>()?;
+        let separations = self
+            .separations
+            .iter()
+            .map(|(a, b, rule)| Ok((ids.resolve_student(a)?, ids.resolve_student(b)?, *rule)))
+            .collect::<Result<Vec<_>, ProblemError>>()?;
+
+        let mut problem =
+            Problem::try_with_distance_fn(seats, want_seats, pair_edges, self.distance_fn)?
+                .with_pair_merge_policy(self.pair_merge_policy)
+                .with_pinned_seats(pins)?
+                .with_forbidden_seats(forbidden)?
+                .with_separations(separations)?;
+        if let Some(penalty) = self.separation_penalty {
+            problem = problem.with_separation_penalty(penalty)?;
+        }
+        Ok((problem, ids))
+    }
+}
+
+/// `ProblemBuilder` が割り当てたキーと ID の双方向対応表。
+#[derive(Debug, Clone)]
+pub struct IdMap {
+    students: Vec<String>,
+    student_ids: HashMap<String, u16>,
+    seats: Vec<String>,
+    seat_ids: HashMap<String, u16>,
+}
+
+impl IdMap {
+    /// 学生キーから StudentId を返します。
+    pub fn student_id(&self, key: &str) -> Option<u16> {
+        self.student_ids.get(key).copied()
+    }
+
+    /// 座席キーから SeatId を返します。
+    pub fn seat_id(&self, key: &str) -> Option<u16> {
+        self.seat_ids.get(key).copied()
+    }
+
+    /// StudentId から学生キーを返します。
+    pub fn student_key(&self, id: u16) -> Option<&str> {
+        self.students.get(id as usize).map(String::as_str)
+    }
+
+    /// SeatId から座席キーを返します。
+    pub fn seat_key(&self, id: u16) -> Option<&str> {
+        self.seats.get(id as usize).map(String::as_str)
+    }
+
+    /// `Individual::by_seat()` の結果を `(学生キー, 座席キー)` の一覧へ変換します。
+    ///
+    /// 座席順に並び、空席は含みません。
+    pub fn assignments(&self, by_seat: &[u16]) -> Vec<(&str, &str)> {
+        by_seat
+            .iter()
+            .enumerate()
+            .filter(|&(_, &student)| student != EMPTY_SEAT)
+            .map(|(seat, &student)| {
+                (
+                    self.students[student as usize].as_str(),
+                    self.seats[seat].as_str(),
+                )
+            })
+            .collect()
+    }
+
+    fn resolve_student(&self, key: &str) -> Result<u16, ProblemError> {
+        self.student_id(key)
+            .ok_or_else(|| ProblemError::UnknownStudentKey {
+                key: key.to_owned(),
+            })
+    }
+
+    fn resolve_seat(&self, key: &str) -> Result<u16, ProblemError> {
+        self.seat_id(key)
+            .ok_or_else(|| ProblemError::UnknownSeatKey {
+                key: key.to_owned(),
+            })
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+/// 開始温度の決め方。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitialTemperature {
+    /// 固定値を使う
+    Fixed(f32),
+    /// 初期解からのランダムジャンプで生じるコスト悪化量の平均を測り、
+    /// その悪化が確率 `acceptance` で受容される温度を開始温度にする
+    ///
+    /// コストの桁が問題ごとに大きく違う場合（座席数が多い、重みが大きい等）に向きます。
+    Auto { acceptance: f32 },
+}
+
+/// ILSA の探索パラメータ。
+///
+/// 既定値は従来のハードコード値と同じです。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ILSAConfig {
+    /// 開始温度
+    pub initial_temperature: InitialTemperature,
+    /// 1 ジャンプごとに温度へ掛ける冷却率（0 < alpha < 1）
+    pub cooling_rate: f32,
+    /// 大ジャンプ 1 回あたりのランダム swap 回数
+    pub jump_size: usize,
+    /// 候補プールの上限 = 返す候補数 × この倍率
+    pub candidate_pool_multiplier: usize,
+    /// 収束判定に使う停滞回数の下限（`budget / 4` をこの範囲に収める）
+    pub min_stall: usize,
+    /// 収束判定に使う停滞回数の上限
+    pub max_stall: usize,
+    /// 収束判定を始める前に必ず行うジャンプ回数
+    pub min_outer_passes: usize,
+    /// 候補として残すコスト幅の比率（幅 = `max(|best| * ratio, candidate_band_min)`）
+    pub candidate_band_ratio: f32,
+    /// 候補として残すコスト幅の最小値
+    pub candidate_band_min: f32,
+    /// 探索の制限時間（`None` なら無制限）
+    ///
+    /// 時間切れになるとジャンプの合間やヒルクライムの途中で探索を打ち切り、
+    /// それまでの最良解を返します。
+    pub time_limit: Option<Duration>,
+    /// 初期解を、ペア距離と分離条件を無視した線形割当問題の最適解から始める
+    ///
+    /// ペアの重みが希望席に比べて小さい問題では、ランダムな初期解より良い出発点になります。
+    /// 並列探索では全チェーンが同じ初期解から始まります。
+    pub assignment_warm_start: bool,
+}
+
+impl Default for ILSAConfig {
+    fn default() -> Self {
+        Self {
+            initial_temperature: InitialTemperature::Fixed(100.0),
+            cooling_rate: 0.95,
+            jump_size: 12,
+            candidate_pool_multiplier: 8,
+            min_stall: 12,
+            max_stall: 80,
+            min_outer_passes: 20,
+            candidate_band_ratio: 0.03,
+            candidate_band_min: 0.5,
+            time_limit: None,
+            assignment_warm_start: false,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+
+/// 探索開始前に検出される、解けない問題のエラー。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// 問題定義が不正
+    InvalidProblem(ProblemError),
+    /// 固定割り当て先が、その学生の禁止席になっている
+    PinnedToForbiddenSeat { student: u16, seat: u16 },
+    /// 禁止席を避けて全員を配置する割り当てが存在しない
+    ///
+    /// `student` は配置先を見つけられなかった学生の一人です。
+    NoFeasibleAssignment { student: u16 },
+    /// 探索しても分離条件と移動人数の上限をすべて満たす配置が見つからなかった
+    ///
+    /// `violations` は見つかった中で最も違反の少ない配置の違反数です。
+    Infeasible { violations: u32 },
+    /// 厳密な割当ソルバーに、ペア距離・分離条件・移動人数上限・過去の隣を持つ問題、またはコスト項を変更した問題が渡された
+    NotAssignmentProblem,
+    /// 厳密ソルバーで扱える座席数の上限を超えている
+    TooLargeForExact { seats: usize, limit: usize },
+    /// 与えられた割り当て (`by_seat`) の長さが座席数と一致しない
+    AssignmentLengthMismatch { seats: usize, len: usize },
+    /// 与えられた割り当てに範囲外の学生がいる
+    AssignedStudentOutOfRange { seat: u16, student: u16 },
+    /// 与えられた割り当てで同じ学生が複数の座席にいる
+    StudentAssignedTwice { student: u16 },
+    /// 与えられた割り当てで座席のない学生がいる
+    StudentNotAssigned { student: u16 },
+    /// 与えられた割り当てが固定割り当てと食い違う
+    PinnedSeatMismatch { student: u16, seat: u16 },
+    /// 与えられた割り当てで学生が禁止席にいる
+    AssignedToForbiddenSeat { student: u16, seat: u16 },
+    /// このソルバーが合計以外の目的関数 (`Objective`) に対応していない
+    UnsupportedObjective,
+    /// このソルバーが既定から変更したコスト項 (`CostTerm`) に対応していない、または項が下界を持たない
+    UnsupportedCostTerm,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidProblem(err) => write!(f, "invalid problem: {}", err),
+            Self::PinnedToForbiddenSeat { student, seat } => write!(
+                f,
+                "student {} is pinned to seat {}, which is forbidden for them",
+                student, seat
+            ),
+            Self::NoFeasibleAssignment { student } => write!(
+                f,
+                "no feasible assignment exists: student {} cannot be seated without using a forbidden seat",
+                student
+            ),
+            Self::Infeasible { violations } => write!(
+                f,
+                "no arrangement satisfying every separation constraint and the move limit was found ({} violations remain)",
+                violations
+            ),
+            Self::NotAssignmentProblem => write!(
+                f,
+                "problem has pair, separation, move-limit, neighbour-history terms or modified cost terms and is not a linear assignment problem"
+            ),
+            Self::TooLargeForExact { seats, limit } => write!(
+                f,
+                "seat count ({}) exceeds the exact solver limit of {}",
+                seats, limit
+            ),
+            Self::AssignmentLengthMismatch { seats, len } => write!(
+                f,
+                "assignment has {} entries but the problem has {} seats",
+                len, seats
+            ),
+            Self::AssignedStudentOutOfRange { seat, student } => write!(
+                f,
+                "seat {} is assigned to student {}, which is out of range",
+                seat, student
+            ),
+            Self::StudentAssignedTwice { student } => {
+                write!(f, "student {} is assigned to more than one seat", student)
+            }
+            Self::StudentNotAssigned { student } => {
+                write!(f, "student {} is not assigned to any seat", student)
+            }
+            Self::PinnedSeatMismatch { student, seat } => write!(
+                f,
+                "student {} is pinned to seat {} but the assignment seats them elsewhere",
+                student, seat
+            ),
+            Self::AssignedToForbiddenSeat { student, seat } => write!(
+                f,
+                "student {} is assigned to seat {}, which is forbidden for them",
+                student, seat
+            ),
+            Self::UnsupportedObjective => {
+                write!(f, "this solver only supports the sum objective")
+            }
+            Self::UnsupportedCostTerm => write!(
+                f,
+                "modified cost terms are not supported by this solver or provide no lower bound"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidProblem(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProblemError> for SolveError {
+    fn from(err: ProblemError) -> Self {
+        Self::InvalidProblem(err)
+    }
+}
+
+/// `Problem` の構築・検証時に検出される入力エラー。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemError {
+    /// 座席数が上限 (`u16::MAX`) を超えている
+    TooManySeats { count: usize },
+    /// 学生数が上限 (`u16::MAX`) を超えている
+    TooManyStudents { count: usize },
+    /// 学生数が座席数を超えている
+    StudentsExceedSeats { students: usize, seats: usize },
+    /// `want_seats` と `pair_edges` の長さ（学生数）が一致しない
+    LengthMismatch {
+        want_seats: usize,
+        pair_edges: usize,
+    },
+    /// 同じ座標の座席が複数ある
+    DuplicateSeat { first: u16, second: u16 },
+    /// `want_seats[student]` に範囲外の座席がある
+    WantSeatOutOfRange { student: u16, seat: u16 },
+    /// `want_seats[student]` の重みが有限値でない
+    NonFiniteWantWeight { student: u16, seat: u16 },
+    /// `pair_edges[student]` に範囲外の学生がある
+    PairStudentOutOfRange { student: u16, other: u16 },
+    /// `pair_edges[student]` の重みが有限値でない
+    NonFinitePairWeight { student: u16, other: u16 },
+    /// 制約に範囲外の学生が指定された
+    StudentOutOfRange { student: u16 },
+    /// 制約に範囲外の座席が指定された
+    SeatOutOfRange { seat: u16 },
+    /// 同じ学生が複数の座席に固定された
+    StudentPinnedTwice { student: u16 },
+    /// 同じ座席に複数の学生が固定された
+    SeatPinnedTwice { seat: u16 },
+    /// 分離条件で同じ学生同士が指定された
+    SelfSeparation { student: u16 },
+    /// 分離条件のペナルティが有限の非負値でない
+    InvalidSeparationPenalty,
+    /// 安定化項の基準配置で同じ学生が複数回指定された
+    StudentReferencedTwice { student: u16 },
+    /// 安定化項の重みが有限の非負値でない
+    InvalidStabilityWeight,
+    /// 履歴の `period` 番目の配置の長さが座席数と一致しない
+    HistoryLengthMismatch {
+        period: usize,
+        seats: usize,
+        len: usize,
+    },
+    /// 履歴の `period` 番目の配置に同じ学生が複数回現れる
+    StudentTwiceInHistory { period: usize, student: u16 },
+    /// 履歴のペナルティが有限の非負値でない、または減衰率が `0..=1` の範囲外
+    InvalidHistoryPenalty,
+    /// 目的関数の重みが有限の非負値でない、または `LexMinMax` の深さが 0
+    InvalidObjective,
+    /// コスト項の重みが有限の非負値でない
+    InvalidCostTermWeight,
+    /// `ProblemBuilder` に同じ学生キーが複数回追加された
+    DuplicateStudentKey { key: String },
+    /// `ProblemBuilder` に同じ座席キーが複数回追加された
+    DuplicateSeatKey { key: String },
+    /// `ProblemBuilder` で未登録の学生キーが参照された
+    UnknownStudentKey { key: String },
+    /// `ProblemBuilder` で未登録の座席キーが参照された
+    UnknownSeatKey { key: String },
+}
+
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManySeats { count } => {
+                write!(
+                    f,
+                    "seat count ({}) exceeds the limit of {}",
+                    count,
+                    u16::MAX
+                )
+            }
+            Self::TooManyStudents { count } => {
+                write!(
+                    f,
+                    "student count ({}) exceeds the limit of {}",
+                    count,
+                    u16::MAX
+                )
+            }
+            Self::StudentsExceedSeats { students, seats } => write!(
+                f,
+                "student count ({}) exceeds seat count ({})",
+                students, seats
+            ),
+            Self::LengthMismatch {
+                want_seats,
+                pair_edges,
+            } => write!(
+                f,
+                "want_seats has {} students but pair_edges has {}",
+                want_seats, pair_edges
+            ),
+            Self::DuplicateSeat { first, second } => write!(
+                f,
+                "seats {} and {} have the same coordinates",
+                first, second
+            ),
+            Self::WantSeatOutOfRange { student, seat } => write!(
+                f,
+                "want_seats[{}] refers to seat {}, which does not exist",
+                student, seat
+            ),
+            Self::NonFiniteWantWeight { student, seat } => write!(
+                f,
+                "want_seats[{}] has a non-finite weight for seat {}",
+                student, seat
+            ),
+            Self::PairStudentOutOfRange { student, other } => write!(
+                f,
+                "pair_edges[{}] refers to student {}, who does not exist",
+                student, other
+            ),
+            Self::NonFinitePairWeight { student, other } => write!(
+                f,
+                "pair_edges[{}] has a non-finite weight for student {}",
+                student, other
+            ),
+            Self::StudentOutOfRange { student } => {
+                write!(f, "student {} does not exist", student)
+            }
+            Self::SeatOutOfRange { seat } => write!(f, "seat {} does not exist", seat),
+            Self::StudentPinnedTwice { student } => {
+                write!(f, "student {} is pinned more than once", student)
+            }
+            Self::SeatPinnedTwice { seat } => write!(f, "seat {} is pinned more than once", seat),
+            Self::SelfSeparation { student } => {
+                write!(f, "student {} cannot be separated from themselves", student)
+            }
+            Self::InvalidSeparationPenalty => {
+                write!(f, "separation penalty must be finite and non-negative")
+            }
+            Self::StudentReferencedTwice { student } => write!(
+                f,
+                "student {} appears more than once in the reference assignment",
+                student
+            ),
+            Self::InvalidStabilityWeight => {
+                write!(f, "stability weight must be finite and non-negative")
+            }
+            Self::HistoryLengthMismatch { period, seats, len } => write!(
+                f,
+                "history entry {} has {} seats but the problem has {}",
+                period, len, seats
+            ),
+            Self::StudentTwiceInHistory { period, student } => write!(
+                f,
+                "student {} appears more than once in history entry {}",
+                student, period
+            ),
+            Self::InvalidHistoryPenalty => write!(
+                f,
+                "history penalties must be finite and non-negative, and decay must be within 0..=1"
+            ),
+            Self::InvalidObjective => write!(
+                f,
+                "objective weights must be finite and non-negative, and the LexMinMax depth must be positive"
+            ),
+            Self::InvalidCostTermWeight => {
+                write!(f, "cost term weight must be finite and non-negative")
+            }
+            Self::DuplicateStudentKey { key } => {
+                write!(f, "student \"{}\" is added more than once", key)
+            }
+            Self::DuplicateSeatKey { key } => write!(f, "seat \"{}\" is added more than once", key),
+            Self::UnknownStudentKey { key } => write!(f, "unknown student \"{}\"", key),
+            Self::UnknownSeatKey { key } => write!(f, "unknown seat \"{}\"", key),
+        }
+    }
+}
+
+impl std::error::Error for ProblemError {}
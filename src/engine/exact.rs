@@ -0,0 +1,365 @@
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::{DistanceFn, engine::problem::DefaultDistanceFn};
+
+use super::{
+    assignment,
+    bound::distance_range,
+    error::SolveError,
+    ilsa::ILSA,
+    individual::Individual,
+    objective::Objective,
+    problem::{EMPTY_SEAT, Problem},
+    rng::SimpleRng,
+};
+
+/// `ExactSolver` が扱える座席数の上限
+pub const EXACT_MAX_SEATS: usize = 12;
+/// `ExactSolver` の探索ノード数上限の既定値
+pub const DEFAULT_EXACT_NODE_LIMIT: u64 = 50_000_000;
+
+/// 相対誤差の許容幅（下界と暫定解の比較に使う）
+const BOUND_TOLERANCE: f32 = 1e-5;
+/// 制限時間を確認する間隔（ノード数）
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// 小規模な問題（座席数 `EXACT_MAX_SEATS` 以下）の厳密ソルバー。
+///
+/// 学生を 1 人ずつ座席へ割り当てる分枝限定法で、希望席・ペア距離・分離条件・安定化項・履歴を含む
+/// すべてのコストを扱います。ノード数や制限時間で打ち切った場合は、
+/// それまでの最良解を最適性未証明として返します。目的関数は `Objective::Sum` のみ、
+/// コスト項は既定のまま（`SolveError::UnsupportedCostTerm`）の問題のみに対応します。
+/// 分離条件に違反する割り当てと移動人数の上限を超える割り当ては枝刈りし
+/// （違反ペナルティは目的関数に含めません）、
+/// 条件を満たす割り当てが一つも見つからなかった場合は `SolveError::Infeasible` を返します。
+pub struct ExactSolver<'p, D: DistanceFn = DefaultDistanceFn> {
+    problem: &'p Problem<D>,
+    node_limit: u64,
+    time_limit: Option<Duration>,
+}
+
+/// `ExactSolver` の結果。
+#[derive(Debug, Clone)]
+pub struct ExactSolution {
+    /// 見つかった最良解
+    pub best: Individual,
+    /// `best` が最適であることを証明できたか（打ち切られた場合は `false`）
+    pub proven_optimal: bool,
+    /// 展開した探索ノード数
+    pub nodes: u64,
+}
+
+impl<'p, D: DistanceFn> ExactSolver<'p, D> {
+    /// 新規インスタンスを作成します。
+    pub fn new(problem: &'p Problem<D>) -> Self {
+        Self {
+            problem,
+            node_limit: DEFAULT_EXACT_NODE_LIMIT,
+            time_limit: None,
+        }
+    }
+
+    /// 探索ノード数の上限を設定します（既定値 `DEFAULT_EXACT_NODE_LIMIT`）。
+    pub fn with_node_limit(mut self, node_limit: u64) -> Self {
+        self.node_limit = node_limit;
+        self
+    }
+
+    /// 制限時間を設定します（既定値は無制限）。
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// 分枝限定法で解きます。
+    ///
+    /// 座席数が `EXACT_MAX_SEATS` を超える場合は `SolveError::TooLargeForExact` を返します。
+    pub fn solve(&self) -> Result<ExactSolution, SolveError> {
+        let problem = self.problem;
+        problem.validate()?;
+        if problem.objective != Objective::Sum {
+            return Err(SolveError::UnsupportedObjective);
+        }
+        if !problem.default_cost_terms {
+            return Err(SolveError::UnsupportedCostTerm);
+        }
+        if problem.seat_count() > EXACT_MAX_SEATS {
+            return Err(SolveError::TooLargeForExact {
+                seats: problem.seat_count(),
+                limit: EXACT_MAX_SEATS,
+            });
+        }
+
+        // 暫定解: ランダム初期解 + ヒルクライム（禁止席の実行可能性もここで判定される）
+        let mut incumbent = Individual::new_random(problem, &mut SimpleRng::new(1))?;
+        ILSA::hill_climb(&mut incumbent, problem);
+
+        // 絶対条件に違反している暫定解は上界に使えない
+        let upper = if incumbent.is_feasible() {
+            incumbent.cost()
+        } else {
+            f32::INFINITY
+        };
+        let mut search = Search::new(problem, upper);
+        search.node_limit = self.node_limit;
+        search.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let root_cost = search.place_pins();
+        if root_cost.is_finite() {
+            search.descend(0, root_cost);
+        }
+
+        let proven_optimal = !search.aborted;
+        let best = match search.best_seat_of {
+            Some(seat_of) => {
+                let mut by_seat = vec![EMPTY_SEAT; problem.seat_count()];
+                for (student, &seat) in seat_of.iter().enumerate() {
+                    by_seat[seat as usize] = student as u16;
+                }
+                Individual::from_assignment(problem, by_seat)
+            }
+            None if incumbent.is_feasible() => incumbent,
+            None => {
+                return Err(SolveError::Infeasible {
+                    violations: incumbent.violations(),
+                });
+            }
+        };
+        if best.violations() > 0 {
+            return Err(SolveError::Infeasible {
+                violations: best.violations(),
+            });
+        }
+        info!(
+            "exact solver done: cost={:.3}, proven_optimal={}, nodes={}",
+            best.cost(),
+            proven_optimal,
+            search.nodes
+        );
+        Ok(ExactSolution {
+            best,
+            proven_optimal,
+            nodes: search.nodes,
+        })
+    }
+}
+
+/// 分枝限定法の探索状態
+struct Search<'a, D: DistanceFn> {
+    problem: &'a Problem<D>,
+    /// 割り当てる順番（固定されていない学生を、ペアの重みが大きい順に）
+    order: Vec<u16>,
+    /// 割り当て途中の `seat_of`（未割り当ては `EMPTY_SEAT`）
+    seat_of: Vec<u16>,
+    used: Vec<bool>,
+    /// 割り当て済みの学生のうち基準配置から移動した人数
+    moves: u32,
+    best_cost: f32,
+    best_seat_of: Option<Vec<u16>>,
+    nodes: u64,
+    node_limit: u64,
+    deadline: Option<Instant>,
+    aborted: bool,
+}
+
+impl<'a, D: DistanceFn> Search<'a, D> {
+    fn new(problem: &'a Problem<D>, best_cost: f32) -> Self {
+        let mut pinned = vec![false; problem.student_count()];
+        for &(student, _) in &problem.pinned {
+            pinned[student as usize] = true;
+        }
+        let mut order: Vec<u16> = (0..problem.student_count() as u16)
+            .filter(|&student| !pinned[student as usize])
+            .collect();
+        let pair_weight = |student: u16| -> f32 {
+            problem.pair_graph[student as usize]
+                .iter()
+                .map(|&(_, w)| w.abs())
+                .sum()
+        };
+        order.sort_by(|&a, &b| pair_weight(b).total_cmp(&pair_weight(a)));
+
+        Self {
+            problem,
+            order,
+            seat_of: vec![EMPTY_SEAT; problem.student_count()],
+            used: vec![false; problem.seat_count()],
+            moves: 0,
+            best_cost,
+            best_seat_of: None,
+            nodes: 0,
+            node_limit: DEFAULT_EXACT_NODE_LIMIT,
+            deadline: None,
+            aborted: false,
+        }
+    }
+
+    /// 固定割り当てを配置し、そのコストを返す
+    fn place_pins(&mut self) -> f32 {
+        let mut cost = 0.0f32;
+        for &(student, seat) in &self.problem.pinned {
+            cost += self.placement_cost(student, seat);
+            self.place(student, seat);
+        }
+        cost
+    }
+
+    fn place(&mut self, student: u16, seat: u16) {
+        self.seat_of[student as usize] = seat;
+        self.used[seat as usize] = true;
+        self.moves += self.problem.has_moved(student, seat) as u32;
+    }
+
+    fn unplace(&mut self, student: u16, seat: u16) {
+        self.seat_of[student as usize] = EMPTY_SEAT;
+        self.used[seat as usize] = false;
+        self.moves -= self.problem.has_moved(student, seat) as u32;
+    }
+
+    /// 割り当て済みの学生に対して、`student` を `seat` に置いたときに増えるコスト
+    ///
+    /// 分離条件に違反する場合や移動人数の上限を超える場合は `f32::INFINITY`（実行不可能な枝）を返す。
+    fn placement_cost(&self, student: u16, seat: u16) -> f32 {
+        let problem = self.problem;
+        let mut cost = problem.linear_cost(student, seat);
+        if problem.has_moved(student, seat)
+            && problem.excess_moves(self.moves + 1) > problem.excess_moves(self.moves)
+        {
+            return f32::INFINITY;
+        }
+        for &(other, w) in &problem.pair_graph[student as usize] {
+            let other_seat = self.seat_of[other as usize];
+            if other_seat != EMPTY_SEAT {
+                cost += w * problem.distance(seat, other_seat) as f32;
+            }
+        }
+        if let Some(edges) = problem.history_pairs.get(student as usize) {
+            for &(other, penalty) in edges {
+                let other_seat = self.seat_of[other as usize];
+                if other_seat != EMPTY_SEAT && problem.are_neighbours(seat, other_seat) {
+                    cost += penalty;
+                }
+            }
+        }
+        if let Some(rules) = problem.separations.get(student as usize) {
+            for &(other, rule) in rules {
+                let other_seat = self.seat_of[other as usize];
+                if other_seat != EMPTY_SEAT && problem.separation_violated(rule, seat, other_seat) {
+                    return f32::INFINITY;
+                }
+            }
+        }
+        cost
+    }
+
+    /// `order[depth..]` の学生を置いたときに増えるコストの下界
+    ///
+    /// 割り当て済みの学生に対する増分は、空いている座席への割当問題として厳密に解き、
+    /// 未割り当て同士のペアは座席間距離の最小値（負の重みは最大値）で見積もります
+    /// （過去の隣のペナルティは 0 とみなします）。
+    fn lower_bound(&self, depth: usize) -> f32 {
+        let problem = self.problem;
+        let students = &self.order[depth..];
+        if students.is_empty() {
+            return 0.0;
+        }
+        let free: Vec<u16> = (0..problem.seat_count() as u16)
+            .filter(|&seat| !self.used[seat as usize])
+            .collect();
+
+        let mut cost = vec![0.0f64; students.len() * free.len()];
+        let mut allowed = vec![true; students.len() * free.len()];
+        for (row, &student) in students.iter().enumerate() {
+            for (col, &seat) in free.iter().enumerate() {
+                let k = row * free.len() + col;
+                if problem.is_forbidden(student, seat) {
+                    allowed[k] = false;
+                    continue;
+                }
+                let step = self.placement_cost(student, seat);
+                allowed[k] = step.is_finite();
+                if allowed[k] {
+                    cost[k] = step as f64;
+                }
+            }
+        }
+        let Ok(col_of_row) =
+            assignment::hungarian_masked(&mut cost, &allowed, students.len(), free.len())
+        else {
+            return f32::INFINITY;
+        };
+        let mut bound: f32 = col_of_row
+            .into_iter()
+            .enumerate()
+            .map(|(row, col)| cost[row * free.len() + col] as f32)
+            .sum();
+
+        let (min_distance, max_distance) = distance_range(problem, &free);
+        for &student in students {
+            for &(other, w) in &problem.pair_graph[student as usize] {
+                if other > student && self.seat_of[other as usize] == EMPTY_SEAT {
+                    let d = if w >= 0.0 { min_distance } else { max_distance };
+                    bound += w * d;
+                }
+            }
+        }
+        bound
+    }
+
+    fn should_abort(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+        self.nodes += 1;
+        let expired = self.nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        if self.nodes > self.node_limit || expired {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    fn prunes(&self, lower: f32) -> bool {
+        !lower.is_finite()
+            || self.best_cost.is_finite()
+                && lower >= self.best_cost - BOUND_TOLERANCE * self.best_cost.abs().max(1.0)
+    }
+
+    fn descend(&mut self, depth: usize, cost: f32) {
+        if self.should_abort() {
+            return;
+        }
+        if depth == self.order.len() {
+            let exact = Individual::calc_cost(self.problem, &self.seat_of);
+            if exact < self.best_cost || self.best_seat_of.is_none() && exact <= self.best_cost {
+                self.best_cost = exact;
+                self.best_seat_of = Some(self.seat_of.clone());
+            }
+            return;
+        }
+
+        // 増分の小さい座席から試す
+        let student = self.order[depth];
+        let mut children: Vec<(u16, f32)> = (0..self.problem.seat_count() as u16)
+            .filter(|&seat| !self.used[seat as usize] && !self.problem.is_forbidden(student, seat))
+            .map(|seat| (seat, self.placement_cost(student, seat)))
+            .filter(|&(_, step)| step.is_finite())
+            .collect();
+        children.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        for (seat, step) in children {
+            let next_cost = cost + step;
+            self.place(student, seat);
+            if !self.prunes(next_cost + self.lower_bound(depth + 1)) {
+                self.descend(depth + 1, next_cost);
+            }
+            self.unplace(student, seat);
+            if self.aborted {
+                return;
+            }
+        }
+    }
+}
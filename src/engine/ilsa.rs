@@ -1,16 +1,33 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+    },
+    time::Instant,
+};
 
 use log::{debug, info};
 
 use crate::{DistanceFn, engine::problem::DefaultDistanceFn};
 
-use super::{individual::Individual, problem::Problem, rng::SimpleRng};
+use super::{
+    assignment,
+    bound::GapReport,
+    config::{ILSAConfig, InitialTemperature},
+    error::SolveError,
+    individual::{Individual, SwapDelta},
+    observer::{Control, Observer, Progress},
+    problem::{EMPTY_SEAT, Problem},
+    rng::SimpleRng,
+};
 
 const IMPROVEMENT_EPSILON: f32 = 1e-6;
-const CONVERGENCE_MIN_STALL: usize = 12;
-const CONVERGENCE_MAX_STALL: usize = 80;
-const MIN_OUTER_PASSES: usize = 20;
-const CANDIDATE_POOL_MULTIPLIER: usize = 8;
+/// 開始温度の自動推定で行うジャンプ回数
+const AUTO_TEMPERATURE_SAMPLES: usize = 32;
+/// 悪化するジャンプが一つもなかった場合の開始温度
+const AUTO_TEMPERATURE_FALLBACK: f32 = 1.0;
 
 /// ILSA 探索エンジン。
 ///
@@ -18,6 +35,9 @@ const CANDIDATE_POOL_MULTIPLIER: usize = 8;
 pub struct ILSA<'p, D: DistanceFn = DefaultDistanceFn> {
     problem: &'p Problem<D>,
     rng: SimpleRng,
+    config: ILSAConfig,
+    cancel: Option<Arc<AtomicBool>>,
+    observer: Option<Box<dyn Observer + 'p>>,
 }
 
 impl<'p, D: DistanceFn> ILSA<'p, D> {
@@ -25,17 +45,83 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
     ///
     /// `seed = 0` の場合はシステム乱数を使って初期化します。
     pub fn new(problem: &'p Problem<D>, seed: u64) -> Self {
+        Self::with_config(problem, seed, ILSAConfig::default())
+    }
+
+    /// 探索パラメータを指定して新規インスタンスを作成します。
+    pub fn with_config(problem: &'p Problem<D>, seed: u64, config: ILSAConfig) -> Self {
         let rng = SimpleRng::new(seed);
-        Self { problem, rng }
+        Self {
+            problem,
+            rng,
+            config,
+            cancel: None,
+            observer: None,
+        }
+    }
+
+    /// 進捗オブザーバーを設定します。
+    ///
+    /// ジャンプ 1 回ごとに呼ばれ、`Control::Stop` を返すと探索を打ち切ります。
+    pub fn with_observer(mut self, observer: impl Observer + 'p) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// 協調的キャンセル用のフラグを設定します。
+    ///
+    /// 呼び出し側がフラグを `true` にすると、ジャンプの合間やヒルクライムの途中で探索を打ち切り、
+    /// それまでの最良解を返します。
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// 探索パラメータを返します。
+    pub fn config(&self) -> &ILSAConfig {
+        &self.config
     }
 
     /// 最良候補1件を返すメインソルバー。
     /// - `budget` – 大ジャンプ回数（例: 300）
-    pub fn solve(&mut self, budget: usize) -> Individual {
-        self.solve_candidates(budget, 1)
+    ///
+    /// 問題定義が不正な場合や、禁止席などの制約を満たす割り当てが存在しない場合は
+    /// `SolveError` を返します。分離条件を満たす配置が見つからなかった場合は
+    /// `SolveError::Infeasible` を返します（返す解は常に `is_feasible()` です）。
+    pub fn solve(&mut self, budget: usize) -> Result<Individual, SolveError> {
+        Ok(self
+            .solve_candidates(budget, 1)?
             .into_iter()
             .next()
-            .expect("solve_candidates always returns at least one candidate")
+            .expect("solve_candidates always returns at least one candidate"))
+    }
+
+    /// `solve` と同じ解に、下界 (`Problem::lower_bound`) と最適性ギャップを添えて返します。
+    ///
+    /// 下界は探索前に計算します。目的関数やコスト項が下界に対応していない場合は
+    /// ギャップを `None` として、解だけを返します。
+    pub fn solve_with_gap(
+        &mut self,
+        budget: usize,
+    ) -> Result<(Individual, Option<GapReport>), SolveError> {
+        let lower_bound = match self.problem.lower_bound() {
+            Ok(bound) => Some(bound),
+            Err(SolveError::UnsupportedObjective | SolveError::UnsupportedCostTerm) => None,
+            Err(err) => return Err(err),
+        };
+        let best = self.solve(budget)?;
+        let Some(lower_bound) = lower_bound else {
+            info!("ILSA gap: lower bound is not available for this problem");
+            return Ok((best, None));
+        };
+        let report = GapReport::new(best.cost(), lower_bound);
+        info!(
+            "ILSA gap: lower_bound={:.3}, gap={:.3} ({:.1}%)",
+            report.lower_bound,
+            report.absolute_gap,
+            report.relative_gap * 100.0
+        );
+        Ok((best, Some(report)))
     }
 
     /// 十分収束した段階で複数候補を返すソルバー。
@@ -44,13 +130,76 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
     ///
     /// 返却候補は「探索履歴中でコストが良い上位N件」を選び、
     /// 最終順序は探索履歴順に保ちます。
-    pub fn solve_candidates(&mut self, budget: usize, max_candidates: usize) -> Vec<Individual> {
+    /// 制限時間 (`ILSAConfig::time_limit`) やキャンセルで打ち切られた場合も、
+    /// それまでの履歴から候補を返します。
+    pub fn solve_candidates(
+        &mut self,
+        budget: usize,
+        max_candidates: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
+        self.run_candidates(None, budget, max_candidates)
+    }
+
+    /// 既存の割り当て `initial` を初期解として探索する `solve`。
+    ///
+    /// 前回の席配置から少しだけ条件を変えて再最適化する場合に使います。
+    /// `initial` は `Individual::from_by_seat` と同じ検査を受け、コストはこの問題で再計算されます。
+    pub fn solve_from(
+        &mut self,
+        initial: &Individual,
+        budget: usize,
+    ) -> Result<Individual, SolveError> {
+        Ok(self
+            .solve_candidates_from(initial, budget, 1)?
+            .into_iter()
+            .next()
+            .expect("solve_candidates always returns at least one candidate"))
+    }
+
+    /// 既存の割り当て `initial` を初期解として探索する `solve_candidates`。
+    pub fn solve_candidates_from(
+        &mut self,
+        initial: &Individual,
+        budget: usize,
+        max_candidates: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
+        self.run_candidates(Some(initial), budget, max_candidates)
+    }
+
+    fn run_candidates(
+        &mut self,
+        initial: Option<&Individual>,
+        budget: usize,
+        max_candidates: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
         let candidate_limit = max_candidates.max(1);
-        let history_limit = candidate_limit
-            .saturating_mul(CANDIDATE_POOL_MULTIPLIER)
-            .max(candidate_limit);
-        let stall_threshold = Self::stall_threshold(budget);
-        let min_outer_passes = budget.min(MIN_OUTER_PASSES);
+        let mut history = self.search_history(initial, budget, candidate_limit)?;
+        Self::retain_feasible(&mut history)?;
+        let candidates = Self::select_top_n_history_ordered(&history, candidate_limit);
+
+        info!(
+            "ILSA done: best_cost={:.3}, returned_candidates={}",
+            Self::min_cost(&candidates),
+            candidates.len()
+        );
+        Ok(candidates)
+    }
+
+    /// 探索を 1 本走らせ、候補プール（探索履歴順）を返す
+    ///
+    /// `initial` があればそれを、なければランダム解（または割当問題の解）を初期解にする。
+    pub(crate) fn search_history(
+        &mut self,
+        initial: Option<&Individual>,
+        budget: usize,
+        candidate_limit: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
+        self.problem.validate()?;
+        let deadline = self.config.time_limit.map(|limit| Instant::now() + limit);
+
+        let history_limit = Self::history_limit(&self.config, candidate_limit);
+        let stall_threshold = self.stall_threshold(budget);
+        let min_outer_passes = budget.min(self.config.min_outer_passes);
 
         info!(
             "ILSA start: students={}, budget={}, candidate_limit={}, stall_threshold={}, min_outer_passes={}",
@@ -61,8 +210,14 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
             min_outer_passes
         );
 
-        let mut current = Individual::new_random(self.problem, &mut self.rng);
-        Self::hill_climb(&mut current, self.problem);
+        let mut current = if let Some(initial) = initial {
+            Individual::from_by_seat(self.problem, initial.by_seat.clone())?
+        } else if self.config.assignment_warm_start {
+            Individual::from_assignment(self.problem, assignment::solve_linear(self.problem)?)
+        } else {
+            Individual::new_random(self.problem, &mut self.rng)?
+        };
+        Self::hill_climb_until(&mut current, self.problem, || self.should_stop(deadline));
         debug!("initial hill-climb complete: cost={:.3}", current.cost());
 
         let mut best = current.clone();
@@ -70,22 +225,43 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
         let mut seen = HashSet::<Vec<u16>>::new();
         Self::push_history_candidate(&mut history, &mut seen, &best, history_limit);
 
-        let mut temp = 100.0f32;
-        let alpha = 0.95f32;
+        let mut temp = self.initial_temperature(&current);
+        let alpha = self.config.cooling_rate;
         let mut stall_iters = 0usize;
+        debug!("initial temperature: {:.3}", temp);
 
         for iter in 0..budget {
-            let mut trial = current.clone();
-            Self::random_k_swaps(&mut trial, 12, self.problem, &mut self.rng);
-            Self::hill_climb(&mut trial, self.problem);
+            if self.should_stop(deadline) {
+                info!(
+                    "ILSA stopped: iter={}, best_cost={:.3}, collected={}",
+                    iter,
+                    best.cost(),
+                    history.len()
+                );
+                break;
+            }
 
-            let delta = trial.cost() - current.cost();
-            let accepted = delta < 0.0 || self.rng.next_f32() < (-delta / temp).exp();
+            let mut trial = current.clone();
+            Self::random_k_swaps(
+                &mut trial,
+                self.config.jump_size,
+                self.problem,
+                &mut self.rng,
+            );
+            Self::hill_climb_until(&mut trial, self.problem, || self.should_stop(deadline));
+
+            // 絶対条件の違反数が増える解は受理せず、同数なら評価の変化で焼きなます
+            let delta = trial.objective_delta(&current, IMPROVEMENT_EPSILON);
+            let accepted = match trial.violations().cmp(&current.violations()) {
+                Ordering::Less => true,
+                Ordering::Greater => false,
+                Ordering::Equal => delta < 0.0 || self.rng.next_f32() < (-delta / temp).exp(),
+            };
             if accepted {
                 current = trial.clone();
             }
 
-            let improved_best = trial.cost() + IMPROVEMENT_EPSILON < best.cost();
+            let improved_best = trial.improves_on(&best, IMPROVEMENT_EPSILON);
             if improved_best {
                 best = trial.clone();
                 stall_iters = 0;
@@ -98,11 +274,10 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
                 stall_iters += 1;
             }
 
-            let candidate_band = Self::candidate_band(best.cost());
-            if improved_best || trial.cost() <= best.cost() + candidate_band {
+            if improved_best || self.within_band(&trial, &best) {
                 Self::push_history_candidate(&mut history, &mut seen, &trial, history_limit);
             }
-            if accepted && current.cost() <= best.cost() + candidate_band {
+            if accepted && self.within_band(&current, &best) {
                 Self::push_history_candidate(&mut history, &mut seen, &current, history_limit);
             }
 
@@ -120,6 +295,25 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
                 );
             }
 
+            if let Some(observer) = self.observer.as_mut() {
+                let progress = Progress {
+                    iteration: iter + 1,
+                    temperature: temp,
+                    current_cost: current.cost(),
+                    best_cost: best.cost(),
+                    stall: stall_iters,
+                    pool_size: history.len(),
+                };
+                if observer.on_iteration(&progress) == Control::Stop {
+                    info!(
+                        "ILSA stopped by observer: iter={}, best_cost={:.3}",
+                        iter + 1,
+                        best.cost()
+                    );
+                    break;
+                }
+            }
+
             if iter + 1 >= min_outer_passes
                 && stall_iters >= stall_threshold
                 && history.len() >= candidate_limit
@@ -143,24 +337,45 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
             &best,
             candidate_limit,
             history_limit,
+            deadline,
         );
+        Ok(history)
+    }
 
-        let candidates = Self::select_top_n_history_ordered(&history, candidate_limit);
+    /// 絶対条件に違反している候補を取り除く
+    ///
+    /// すべての候補が違反している場合は `SolveError::Infeasible` を返す。
+    pub(crate) fn retain_feasible(history: &mut Vec<Individual>) -> Result<(), SolveError> {
+        let violations = history
+            .iter()
+            .map(Individual::violations)
+            .min()
+            .unwrap_or(0);
+        if violations > 0 {
+            info!("no feasible candidate found: violations={}", violations);
+            return Err(SolveError::Infeasible { violations });
+        }
+        history.retain(Individual::is_feasible);
+        Ok(())
+    }
 
-        info!(
-            "ILSA done: best_cost={:.3}, returned_candidates={}",
-            best.cost(),
-            candidates.len()
-        );
-        candidates
+    /// 候補プールの上限件数
+    #[inline]
+    pub(crate) fn history_limit(config: &ILSAConfig, candidate_limit: usize) -> usize {
+        candidate_limit
+            .saturating_mul(config.candidate_pool_multiplier)
+            .max(candidate_limit)
     }
 
     #[inline]
-    fn compare_cost(a: &Individual, b: &Individual) -> Ordering {
-        a.cost().partial_cmp(&b.cost()).unwrap_or(Ordering::Equal)
+    pub(crate) fn min_cost(candidates: &[Individual]) -> f32 {
+        candidates
+            .iter()
+            .map(Individual::cost)
+            .fold(f32::INFINITY, f32::min)
     }
 
-    fn push_history_candidate(
+    pub(crate) fn push_history_candidate(
         history: &mut Vec<Individual>,
         seen: &mut HashSet<Vec<u16>>,
         candidate: &Individual,
@@ -173,20 +388,22 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
 
         history.push(candidate.clone());
 
-        if history.len() > history_limit {
-            if let Some((worst_idx, worst)) = history
+        if history.len() > history_limit
+            && let Some((worst_idx, worst)) = history
                 .iter()
                 .enumerate()
-                .max_by(|(_, a), (_, b)| Self::compare_cost(a, b))
+                .max_by(|(_, a), (_, b)| a.compare(b))
                 .map(|(idx, item)| (idx, item.clone()))
-            {
-                history.swap_remove(worst_idx);
-                seen.remove(&worst.by_seat().to_vec());
-            }
+        {
+            history.swap_remove(worst_idx);
+            seen.remove(&worst.by_seat().to_vec());
         }
     }
 
-    fn select_top_n_history_ordered(history: &[Individual], n: usize) -> Vec<Individual> {
+    pub(crate) fn select_top_n_history_ordered(
+        history: &[Individual],
+        n: usize,
+    ) -> Vec<Individual> {
         if history.is_empty() {
             return Vec::new();
         }
@@ -195,7 +412,7 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
         ranked.sort_by(|&ia, &ib| {
             let a = &history[ia];
             let b = &history[ib];
-            Self::compare_cost(a, b).then_with(|| ia.cmp(&ib))
+            a.compare(b).then_with(|| ia.cmp(&ib))
         });
 
         let base_count = n.min(history.len());
@@ -223,26 +440,26 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
         best: &Individual,
         candidate_limit: usize,
         history_limit: usize,
+        deadline: Option<Instant>,
     ) {
         if history.len() >= candidate_limit {
             return;
         }
 
-        let seat_count = self.problem.student_count().max(1);
+        let seat_count = self.problem.movable_seats.len().max(1);
         let extra_attempts = candidate_limit.saturating_mul(32).max(32);
 
         for attempt in 0..extra_attempts {
-            if history.len() >= candidate_limit {
+            if history.len() >= candidate_limit || self.should_stop(deadline) {
                 break;
             }
 
             let mut trial = best.clone();
             let jump_k = ((attempt % seat_count) + 2).min(seat_count.max(2));
             Self::random_k_swaps(&mut trial, jump_k, self.problem, &mut self.rng);
-            Self::hill_climb(&mut trial, self.problem);
+            Self::hill_climb_until(&mut trial, self.problem, || self.should_stop(deadline));
 
-            let candidate_band = Self::candidate_band(best.cost());
-            if trial.cost() <= best.cost() + candidate_band {
+            if self.within_band(&trial, best) {
                 Self::push_history_candidate(history, seen, &trial, history_limit);
             }
         }
@@ -256,37 +473,103 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
         }
     }
 
+    /// 制限時間切れ、またはキャンセル要求があるか
     #[inline]
-    fn stall_threshold(budget: usize) -> usize {
+    fn should_stop(&self, deadline: Option<Instant>) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|flag| flag.load(AtomicOrdering::Relaxed))
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    #[inline]
+    fn stall_threshold(&self, budget: usize) -> usize {
         (budget / 4)
-            .max(CONVERGENCE_MIN_STALL)
-            .min(CONVERGENCE_MAX_STALL)
+            .max(self.config.min_stall)
+            .min(self.config.max_stall)
     }
 
     #[inline]
-    fn candidate_band(best_cost: f32) -> f32 {
-        (best_cost.abs() * 0.03).max(0.5)
+    fn candidate_band(&self, best_cost: f32) -> f32 {
+        (best_cost.abs() * self.config.candidate_band_ratio).max(self.config.candidate_band_min)
+    }
+
+    /// `ind` が候補プールに入れる範囲（違反数が `best` 以下で、コストが許容幅内）にあるか
+    #[inline]
+    fn within_band(&self, ind: &Individual, best: &Individual) -> bool {
+        ind.violations() <= best.violations()
+            && ind.cost() <= best.cost() + self.candidate_band(best.cost())
+    }
+
+    /// 設定に従って開始温度を決める
+    pub(crate) fn initial_temperature(&mut self, initial: &Individual) -> f32 {
+        let acceptance = match self.config.initial_temperature {
+            InitialTemperature::Fixed(temp) => return temp,
+            InitialTemperature::Auto { acceptance } => acceptance,
+        };
+
+        // 初期解からのジャンプで生じる悪化量の平均 Δ を測り、exp(-Δ / T) = acceptance となる T を使う
+        let mut uphill_sum = 0.0f32;
+        let mut uphill_count = 0usize;
+        for _ in 0..AUTO_TEMPERATURE_SAMPLES {
+            let mut trial = initial.clone();
+            Self::random_k_swaps(
+                &mut trial,
+                self.config.jump_size,
+                self.problem,
+                &mut self.rng,
+            );
+            let delta = trial.cost() - initial.cost();
+            if delta > IMPROVEMENT_EPSILON {
+                uphill_sum += delta;
+                uphill_count += 1;
+            }
+        }
+        if uphill_count == 0 {
+            return AUTO_TEMPERATURE_FALLBACK;
+        }
+        let mean_uphill = uphill_sum / uphill_count as f32;
+        -mean_uphill / acceptance.clamp(f32::EPSILON, 1.0 - f32::EPSILON).ln()
     }
 
     /// 2-swap ヒルクライム（最良改善を即時採用）
-    fn hill_climb(ind: &mut Individual, prob: &Problem<D>) {
-        let n = ind.by_seat.len();
+    ///
+    /// 固定座席は swap 対象に含めない。絶対条件の違反数を減らす swap を、コストの改善より優先する。
+    pub(crate) fn hill_climb(ind: &mut Individual, prob: &Problem<D>) {
+        Self::hill_climb_until(ind, prob, || false);
+    }
+
+    /// `should_stop` が `true` を返したら途中で打ち切る `hill_climb`
+    ///
+    /// 大きな問題では 1 回の近傍走査も長いため、走査の行ごとに確認する。
+    fn hill_climb_until(ind: &mut Individual, prob: &Problem<D>, should_stop: impl Fn() -> bool) {
+        let movable = &prob.movable_seats;
+        let n = movable.len();
         if n < 2 {
-            ind.cost = Individual::calc_cost(prob, &ind.seat_of);
+            ind.resync(prob);
             return;
         }
 
         let mut accepted_moves = 0usize;
 
-        loop {
-            let mut best_move: Option<(usize, usize, f32)> = None;
+        'climb: loop {
+            let mut best_move: Option<(usize, usize, SwapDelta)> = None;
 
-            for i in 0..n {
-                for j in (i + 1)..n {
+            for (k, &seat_i) in movable.iter().enumerate() {
+                if should_stop() {
+                    debug!("hill_climb stopped: accepted_moves={}", accepted_moves);
+                    break 'climb;
+                }
+                let i = seat_i as usize;
+                for &seat_j in &movable[(k + 1)..] {
+                    let j = seat_j as usize;
+                    if ind.by_seat[i] == EMPTY_SEAT && ind.by_seat[j] == EMPTY_SEAT {
+                        continue;
+                    }
                     let delta = ind.delta_swap_cost(prob, i, j);
-                    if delta < -IMPROVEMENT_EPSILON {
+                    if delta.improves(IMPROVEMENT_EPSILON) {
                         match best_move {
-                            Some((_, _, best_delta)) if delta >= best_delta => {}
+                            Some((_, _, best_delta)) if delta.compare(&best_delta).is_ge() => {}
                             _ => best_move = Some((i, j, delta)),
                         }
                     }
@@ -297,22 +580,20 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
                 break;
             };
 
-            let old_cost = ind.cost();
-            ind.apply_swap(i, j, delta);
-            let exact_cost = Individual::calc_cost(prob, &ind.seat_of);
+            let before = ind.clone();
+            ind.apply_swap(prob, i, j, delta);
+            ind.resync(prob);
 
-            if exact_cost + IMPROVEMENT_EPSILON < old_cost {
-                ind.cost = exact_cost;
+            if ind.improves_on(&before, IMPROVEMENT_EPSILON) {
                 accepted_moves += 1;
             } else {
-                ind.apply_swap(i, j, -delta);
-                ind.cost = old_cost;
+                *ind = before;
                 break;
             }
         }
 
         // 差分更新での誤差を吸収するため、終了時に厳密コストへ同期する
-        ind.cost = Individual::calc_cost(prob, &ind.seat_of);
+        ind.resync(prob);
         debug!(
             "hill_climb done: accepted_moves={} synced_cost={:.3}",
             accepted_moves,
@@ -320,27 +601,24 @@ impl<'p, D: DistanceFn> ILSA<'p, D> {
         );
     }
 
-    /// 座席を k 回ランダム swap して大ジャンプを作る
+    /// 固定されていない座席を k 回ランダム swap して大ジャンプを作る（禁止席への移動は行わない）
     fn random_k_swaps(ind: &mut Individual, k: usize, prob: &Problem<D>, rng: &mut SimpleRng) {
-        let n = ind.by_seat.len();
+        let movable = &prob.movable_seats;
+        let n = movable.len();
         if n < 2 {
-            ind.cost = Individual::calc_cost(prob, &ind.seat_of);
-            debug!("random_k_swaps skipped: seat_count < 2");
+            ind.resync(prob);
+            debug!("random_k_swaps skipped: movable_seat_count < 2");
             return;
         }
         for _ in 0..k {
-            let i = rng.gen_range(0..n);
-            let j = rng.gen_range(0..n);
-            if i == j {
+            let i = movable[rng.gen_range(0..n)] as usize;
+            let j = movable[rng.gen_range(0..n)] as usize;
+            if i == j || !ind.swap_allowed(prob, i, j) {
                 continue;
             }
-            ind.by_seat.swap(i, j);
-            let a = ind.by_seat[i] as usize;
-            let b = ind.by_seat[j] as usize;
-            ind.seat_of[a] = i as u16;
-            ind.seat_of[b] = j as u16;
+            ind.swap_seats(i, j);
         }
         // ジャンプ後にコスト再評価（安全策）
-        ind.cost = Individual::calc_cost(prob, &ind.seat_of);
+        ind.resync(prob);
     }
 }
@@ -1,155 +1,796 @@
+use std::cmp::Ordering;
+
 use crate::DistanceFn;
 
-use super::{problem::Problem, rng::SimpleRng};
+use super::{
+    error::SolveError,
+    objective::{CostRanking, Objective},
+    problem::{EMPTY_SEAT, Problem},
+    rng::SimpleRng,
+    term::Swap,
+};
+
+/// 2 座席 swap による評価の変化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SwapDelta {
+    /// 違反している絶対条件の数の変化
+    pub(crate) violations: i32,
+    /// コストの変化（違反ペナルティを含む。禁止席へ移動する swap は `f32::INFINITY`）
+    pub(crate) cost: f32,
+    /// `LexMinMax` で、2 番目以降の上位コストのうち最初に変わる値の変化（変わらなければ 0）
+    pub(crate) tail: f32,
+}
+
+impl SwapDelta {
+    const ZERO: Self = Self {
+        violations: 0,
+        cost: 0.0,
+        tail: 0.0,
+    };
+
+    /// 評価が改善するか（違反数が減るか、同数でコストが `epsilon` を超えて下がるか、
+    /// コストが変わらず 2 番目以降の上位コストが下がる）
+    #[inline]
+    pub(crate) fn improves(&self, epsilon: f32) -> bool {
+        self.violations < 0
+            || (self.violations == 0
+                && (self.cost < -epsilon || (self.cost <= epsilon && self.tail < -epsilon)))
+    }
+
+    /// 違反数、コスト、2 番目以降の上位コストの順に比べる
+    #[inline]
+    pub(crate) fn compare(&self, other: &Self) -> Ordering {
+        self.violations
+            .cmp(&other.violations)
+            .then_with(|| self.cost.total_cmp(&other.cost))
+            .then_with(|| self.tail.total_cmp(&other.tail))
+    }
+}
 
 /// 個体：座席割当とコスト
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Individual {
-    /// index = SeatId, value = StudentId
+    /// index = SeatId, value = StudentId（空席は `EMPTY_SEAT`）
     pub(crate) by_seat: Vec<u16>,
     /// index = StudentId, value = SeatId
     pub(crate) seat_of: Vec<u16>,
     pub(crate) cost: f32,
+    /// 違反している絶対条件の数（分離条件 + 移動人数上限の超過人数）
+    pub(crate) violations: u32,
+    /// 安定化項の基準配置から移動した学生数
+    pub(crate) moves: u32,
+    /// 学生ごとの違反ペナルティを除いたコスト（目的関数が `Sum` のときは空）
+    pub(crate) soft_costs: Vec<f32>,
+    /// `soft_costs` の多重集合（目的関数が `Sum` のときは空）
+    pub(crate) ranking: CostRanking,
+    /// `LexMinMax` で比べる 2 番目以降の上位コスト（大きい順。それ以外の目的関数では空）
+    pub(crate) tail: Vec<f32>,
 }
 
 impl Individual {
-    pub(crate) fn new_random<D: DistanceFn>(problem: &Problem<D>, rng: &mut SimpleRng) -> Self {
-        let mut by_seat: Vec<u16> = (0..problem.student_count() as u16).collect();
-        rng.shuffle(&mut by_seat);
-        let seat_of = Self::inverse(&by_seat);
-        let cost = Self::calc_cost(problem, &seat_of);
-        Self {
+    /// 固定割り当てと禁止席を満たすランダムな初期解を生成します。
+    ///
+    /// `problem` は検証済み（`Problem::validate`）であることを前提とします。
+    pub(crate) fn new_random<D: DistanceFn>(
+        problem: &Problem<D>,
+        rng: &mut SimpleRng,
+    ) -> Result<Self, SolveError> {
+        let student_count = problem.student_count();
+        debug_assert!(student_count <= problem.seat_count());
+        if let Some(&(student, seat)) = problem
+            .pinned
+            .iter()
+            .find(|&&(student, seat)| problem.is_forbidden(student, seat))
+        {
+            return Err(SolveError::PinnedToForbiddenSeat { student, seat });
+        }
+
+        let mut by_seat = vec![EMPTY_SEAT; problem.seat_count()];
+        let mut pinned_student = vec![false; student_count];
+        for &(student, seat) in &problem.pinned {
+            by_seat[seat as usize] = student;
+            pinned_student[student as usize] = true;
+        }
+
+        // 固定されていない学生を残りの座席へランダムに配置（余りは空席）
+        let free_seats = &problem.movable_seats;
+        let mut fill: Vec<u16> = (0..student_count as u16)
+            .filter(|&student| !pinned_student[student as usize])
+            .collect();
+        fill.resize(free_seats.len(), EMPTY_SEAT);
+        rng.shuffle(&mut fill);
+        for (&seat, student) in free_seats.iter().zip(fill) {
+            by_seat[seat as usize] = student;
+        }
+        if !problem.forbidden.is_empty() {
+            Self::repair_forbidden(problem, &mut by_seat, rng)?;
+        }
+
+        let seat_of = Self::inverse(&by_seat, student_count);
+        let mut ind = Self {
             by_seat,
             seat_of,
-            cost,
+            cost: 0.0,
+            violations: 0,
+            moves: 0,
+            soft_costs: Vec::new(),
+            ranking: CostRanking::default(),
+            tail: Vec::new(),
+        };
+        ind.resync(problem);
+        Ok(ind)
+    }
+
+    /// 既存の座席割り当て（`seat_id -> student_id`、空席は `EMPTY_SEAT`）から個体を作ります。
+    ///
+    /// 全学生がちょうど 1 回ずつ現れること、固定割り当てと禁止席を守っていることを検査し、
+    /// コストを計算します。分離条件の違反は許されます（`violations` に数えられます）。
+    pub fn from_by_seat<D: DistanceFn>(
+        problem: &Problem<D>,
+        by_seat: Vec<u16>,
+    ) -> Result<Self, SolveError> {
+        problem.validate()?;
+        if by_seat.len() != problem.seat_count() {
+            return Err(SolveError::AssignmentLengthMismatch {
+                seats: problem.seat_count(),
+                len: by_seat.len(),
+            });
+        }
+        let student_count = problem.student_count();
+        let mut seated = vec![false; student_count];
+        for (seat, &student) in by_seat.iter().enumerate() {
+            if student == EMPTY_SEAT {
+                continue;
+            }
+            if student as usize >= student_count {
+                return Err(SolveError::AssignedStudentOutOfRange {
+                    seat: seat as u16,
+                    student,
+                });
+            }
+            if std::mem::replace(&mut seated[student as usize], true) {
+                return Err(SolveError::StudentAssignedTwice { student });
+            }
+            if problem.is_forbidden(student, seat as u16) {
+                return Err(SolveError::AssignedToForbiddenSeat {
+                    student,
+                    seat: seat as u16,
+                });
+            }
+        }
+        if let Some(student) = seated.iter().position(|&ok| !ok) {
+            return Err(SolveError::StudentNotAssigned {
+                student: student as u16,
+            });
+        }
+        if let Some(&(student, seat)) = problem
+            .pinned
+            .iter()
+            .find(|&&(student, seat)| by_seat[seat as usize] != student)
+        {
+            return Err(SolveError::PinnedSeatMismatch { student, seat });
+        }
+        Ok(Self::from_assignment(problem, by_seat))
+    }
+
+    /// 割り当て済みの `by_seat` から個体を作り、コストを計算します（制約は検査しません）。
+    pub(crate) fn from_assignment<D: DistanceFn>(problem: &Problem<D>, by_seat: Vec<u16>) -> Self {
+        let seat_of = Self::inverse(&by_seat, problem.student_count());
+        let mut ind = Self {
+            by_seat,
+            seat_of,
+            cost: 0.0,
+            violations: 0,
+            moves: 0,
+            soft_costs: Vec::new(),
+            ranking: CostRanking::default(),
+            tail: Vec::new(),
+        };
+        ind.resync(problem);
+        ind
+    }
+
+    /// 禁止席に座っている学生を、増加路法（二部マッチング）で許可された座席へ移す
+    fn repair_forbidden<D: DistanceFn>(
+        problem: &Problem<D>,
+        by_seat: &mut [u16],
+        rng: &mut SimpleRng,
+    ) -> Result<(), SolveError> {
+        let mut unplaced = Vec::new();
+        for &seat in &problem.movable_seats {
+            let student = by_seat[seat as usize];
+            if student != EMPTY_SEAT && problem.is_forbidden(student, seat) {
+                by_seat[seat as usize] = EMPTY_SEAT;
+                unplaced.push(student);
+            }
+        }
+        if unplaced.is_empty() {
+            return Ok(());
+        }
+
+        // 探索順をずらして、修復結果が毎回同じ座席に偏らないようにする
+        let mut seats = problem.movable_seats.clone();
+        let offset = rng.gen_range(0..seats.len());
+        seats.rotate_left(offset);
+
+        let mut visited = vec![false; problem.seat_count()];
+        for student in unplaced {
+            visited.fill(false);
+            if !Self::augment(problem, &seats, by_seat, &mut visited, student) {
+                return Err(SolveError::NoFeasibleAssignment { student });
+            }
         }
+        Ok(())
     }
 
-    /// `by_seat` から `seat_of` を生成
+    fn augment<D: DistanceFn>(
+        problem: &Problem<D>,
+        seats: &[u16],
+        by_seat: &mut [u16],
+        visited: &mut [bool],
+        student: u16,
+    ) -> bool {
+        for &seat in seats {
+            if visited[seat as usize] || problem.is_forbidden(student, seat) {
+                continue;
+            }
+            visited[seat as usize] = true;
+            let occupant = by_seat[seat as usize];
+            if occupant == EMPTY_SEAT || Self::augment(problem, seats, by_seat, visited, occupant) {
+                by_seat[seat as usize] = student;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// `by_seat` から `seat_of` を生成（空席は読み飛ばす）
     #[inline]
-    fn inverse(by_seat: &[u16]) -> Vec<u16> {
-        let mut seat_of = vec![0u16; by_seat.len()];
+    fn inverse(by_seat: &[u16], student_count: usize) -> Vec<u16> {
+        let mut seat_of = vec![0u16; student_count];
         for (seat, &student) in by_seat.iter().enumerate() {
-            seat_of[student as usize] = seat as u16;
+            if student != EMPTY_SEAT {
+                seat_of[student as usize] = seat as u16;
+            }
+        }
+        seat_of
+    }
+
+    /// 厳密なコスト・違反数・移動人数を再計算して同期する
+    pub(crate) fn resync<D: DistanceFn>(&mut self, problem: &Problem<D>) {
+        self.violations = Self::count_violations(problem, &self.seat_of);
+        self.moves = Self::count_moves(problem, &self.seat_of);
+        if problem.objective == Objective::Sum {
+            self.cost = Self::calc_cost(problem, &self.seat_of);
+            self.soft_costs.clear();
+            self.ranking = CostRanking::default();
+            self.tail.clear();
+            return;
+        }
+        self.soft_costs = Self::soft_costs(problem, &self.seat_of);
+        self.ranking = CostRanking::from_costs(&self.soft_costs);
+        self.tail = Self::lex_tail(problem, &self.ranking);
+        self.cost = Self::objective_cost(problem, &self.seat_of, &self.soft_costs, self.violations);
+    }
+
+    /// 基準配置から移動した学生数を数える
+    pub(crate) fn count_moves<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> u32 {
+        if problem.reference_seat.is_empty() {
+            return 0;
         }
         seat_of
+            .iter()
+            .enumerate()
+            .filter(|&(student, &seat)| problem.has_moved(student as u16, seat))
+            .count() as u32
+    }
+
+    /// 違反している絶対条件の数（分離条件 + 移動人数上限の超過人数）を数える
+    pub(crate) fn count_violations<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> u32 {
+        let mut violations = problem.excess_moves(Self::count_moves(problem, seat_of));
+        for (a, rules) in problem.separations.iter().enumerate() {
+            for &(b, rule) in rules {
+                if a as u16 >= b {
+                    continue;
+                }
+                if problem.separation_violated(rule, seat_of[a], seat_of[b as usize]) {
+                    violations += 1;
+                }
+            }
+        }
+        violations
     }
 
-    /// 総コストを計算
+    /// 総コストを計算（安定化項と絶対条件の違反ペナルティを含む）
+    ///
+    /// 目的関数が `Sum` のときは、学生ごとのコスト (`student_cost`) を学生 ID 順に足し、
+    /// 最後に移動人数上限の超過ペナルティと学生に帰属しないコスト項を加える。
+    /// `CostBreakdown::total` と完全に一致させるため、この順序は変えないこと。
+    /// `Sum` 以外では `objective_cost` の値で、`CostBreakdown::total` とは一致しない
+    /// （`CostBreakdown::objective` として報告する）。
     pub(crate) fn calc_cost<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> f32 {
+        if problem.objective != Objective::Sum {
+            let violations = Self::count_violations(problem, seat_of);
+            let soft_costs = Self::soft_costs(problem, seat_of);
+            return Self::objective_cost(problem, seat_of, &soft_costs, violations);
+        }
         let mut cost = 0.0f32;
+        for student in 0..seat_of.len() {
+            cost += Self::student_cost(problem, seat_of, student as u16);
+        }
+        cost + Self::move_limit_penalty(problem, seat_of)
+            + Self::shared_terms_cost(problem, seat_of)
+    }
 
-        // 個人希望
-        for (student, wants) in problem.want_seats.iter().enumerate() {
-            if wants.is_empty() {
-                continue;
+    /// 学生に帰属しないコスト項（`CostTerm::per_student` が `false`）の重み付き合計
+    pub(crate) fn shared_terms_cost<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> f32 {
+        let mut cost = 0.0f32;
+        for (weight, term) in &problem.cost_terms {
+            if !term.per_student() {
+                cost += weight * term.evaluate(problem, seat_of);
             }
-            let seat = seat_of[student] as u16;
-            let mut best = f32::INFINITY;
-            for &(ws, w) in wants {
-                let d = problem.distance(seat, ws) as f32 * w;
-                if d < best {
-                    best = d;
+        }
+        cost
+    }
+
+    /// 学生 1 人に帰属するコスト
+    ///
+    /// 過去の座席・移動の重みと学生ごとに分けられるコスト項の負担分は本人に、
+    /// 過去の隣・分離条件の違反は 2 人で半分ずつ帰属させる。
+    pub(crate) fn student_cost<D: DistanceFn>(
+        problem: &Problem<D>,
+        seat_of: &[u16],
+        student: u16,
+    ) -> f32 {
+        let seat = seat_of[student as usize];
+        let mut cost = Self::soft_cost(problem, student, |other| seat_of[other as usize]);
+
+        // 分離条件
+        if let Some(rules) = problem.separations.get(student as usize) {
+            for &(other, rule) in rules {
+                if problem.separation_violated(rule, seat, seat_of[other as usize]) {
+                    cost += 0.5 * problem.separation_penalty;
                 }
             }
-            cost += best;
         }
+        cost
+    }
 
-        // ペア距離
-        for (a, edges) in problem.pair_edges.iter().enumerate() {
-            let seat_a = seat_of[a] as u16;
-            for &(b, w) in edges {
-                if a as u16 >= b {
-                    continue;
+    /// 学生 1 人に帰属するコストのうち、絶対条件の違反ペナルティを除いた部分
+    ///
+    /// `seat_of` は学生 ID から座席を返す関数（swap 後の座席を評価するため）。
+    /// `student_cost` と同じ順序で足すこと。
+    fn soft_cost<D: DistanceFn>(
+        problem: &Problem<D>,
+        student: u16,
+        seat_of: impl Fn(u16) -> u16,
+    ) -> f32 {
+        let s = student as usize;
+        let seat = seat_of(student);
+        let mut cost = problem.history_seat_cost(student, seat);
+        if problem.has_moved(student, seat) {
+            cost += problem.move_weight;
+        }
+
+        // コスト項（組み込みの希望席・ペア距離を含む）
+        for (weight, term) in &problem.cost_terms {
+            if term.per_student() {
+                cost += weight * term.student_cost(problem, student, &seat_of);
+            }
+        }
+
+        // 過去の隣
+        if let Some(edges) = problem.history_pairs.get(s) {
+            for &(other, penalty) in edges {
+                if problem.are_neighbours(seat, seat_of(other)) {
+                    cost += 0.5 * penalty;
                 }
-                let seat_b = seat_of[b as usize] as u16;
-                cost += w * (problem.distance(seat_a, seat_b) as f32);
             }
         }
         cost
     }
 
-    /// 2 座席 swap の差分コストを計算
-    pub(crate) fn delta_swap_cost<D: DistanceFn>(&self, problem: &Problem<D>, i: usize, j: usize) -> f32 {
-        if i == j {
-            return 0.0;
+    /// 全学生の `soft_cost`（index = StudentId）
+    fn soft_costs<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> Vec<f32> {
+        (0..seat_of.len() as u16)
+            .map(|student| Self::soft_cost(problem, student, |other| seat_of[other as usize]))
+            .collect()
+    }
+
+    /// `Sum` 以外の目的関数の値に、違反ペナルティと学生に帰属しないコスト項を加えたコスト
+    fn objective_cost<D: DistanceFn>(
+        problem: &Problem<D>,
+        seat_of: &[u16],
+        soft_costs: &[f32],
+        violations: u32,
+    ) -> f32 {
+        let mut sum = 0.0f32;
+        for &cost in soft_costs {
+            sum += cost;
         }
-        let a = self.by_seat[i] as usize;
-        let b = self.by_seat[j] as usize;
-        let seat_a_old = i as u16;
-        let seat_a_new = j as u16;
-        let seat_b_old = j as u16;
-        let seat_b_new = i as u16;
+        let mut top = soft_costs.to_vec();
+        top.sort_by(|a, b| b.total_cmp(a));
+        top.truncate(problem.objective.depth());
+        problem.objective.scalarize(sum, &top)
+            + problem.separation_penalty * violations as f32
+            + Self::shared_terms_cost(problem, seat_of)
+    }
 
-        let mut delta = 0.0f32;
+    /// `LexMinMax` で比べる 2 番目以降の上位コスト（それ以外の目的関数では空）
+    fn lex_tail<D: DistanceFn>(problem: &Problem<D>, ranking: &CostRanking) -> Vec<f32> {
+        match problem.objective {
+            Objective::LexMinMax { depth } if depth > 1 => {
+                ranking.top_after(depth, &[], &[]).split_off(1)
+            }
+            _ => Vec::new(),
+        }
+    }
 
-        // 個人希望 A,B のみ再計算
-        for &student in &[a, b] {
-            let wants = &problem.want_seats[student];
-            if wants.is_empty() {
+    /// 学生 `a`, `b` が動いたときに `soft_cost` が変わりうる学生（重複なし）
+    fn affected_students<D: DistanceFn>(problem: &Problem<D>, a: u16, b: u16) -> Vec<u16> {
+        let mut students = Vec::new();
+        for student in [a, b] {
+            if student == EMPTY_SEAT {
                 continue;
             }
-            let old_seat = self.seat_of[student] as u16;
-            let new_seat = if student == a { seat_a_new } else { seat_b_new };
-            let old_best = wants
-                .iter()
-                .map(|&(ws, w)| problem.distance(old_seat, ws) as f32 * w)
-                .fold(f32::INFINITY, f32::min);
-            let new_best = wants
-                .iter()
-                .map(|&(ws, w)| problem.distance(new_seat, ws) as f32 * w)
-                .fold(f32::INFINITY, f32::min);
-            delta += new_best - old_best;
+            let s = student as usize;
+            students.push(student);
+            students.extend(problem.pair_graph[s].iter().map(|&(other, _)| other));
+            if let Some(edges) = problem.history_pairs.get(s) {
+                students.extend(edges.iter().map(|&(other, _)| other));
+            }
         }
+        students.sort_unstable();
+        students.dedup();
+        students
+    }
 
-        // ペア距離：A, B 関連のみ
-        for &(other, w) in &problem.pair_edges[a] {
-            let other_idx = other as usize;
-            let seat_other_old = self.seat_of[other_idx] as u16;
-            let seat_other_new = if other_idx == b {
-                seat_b_new
-            } else {
-                seat_other_old
+    /// 移動人数上限の超過ペナルティ（特定の学生には帰属しない）
+    pub(crate) fn move_limit_penalty<D: DistanceFn>(problem: &Problem<D>, seat_of: &[u16]) -> f32 {
+        let excess = problem.excess_moves(Self::count_moves(problem, seat_of));
+        if excess == 0 {
+            0.0
+        } else {
+            problem.separation_penalty * excess as f32
+        }
+    }
+
+    /// 2 座席 swap による違反数とコストの変化を計算
+    ///
+    /// どちらかの座席が空席の場合は、もう一方の学生が空席へ移動する差分になります。
+    /// 禁止席へ移動する swap はコストの変化が `f32::INFINITY` になります。
+    pub(crate) fn delta_swap_cost<D: DistanceFn>(
+        &self,
+        problem: &Problem<D>,
+        i: usize,
+        j: usize,
+    ) -> SwapDelta {
+        if i == j {
+            return SwapDelta::ZERO;
+        }
+        if !self.swap_allowed(problem, i, j) {
+            return SwapDelta {
+                violations: 0,
+                cost: f32::INFINITY,
+                tail: 0.0,
             };
-            let old = problem.distance(seat_a_old, seat_other_old) as f32;
-            let new = problem.distance(seat_a_new, seat_other_new) as f32;
-            delta += w * (new - old);
-        }
-        for &(other, w) in &problem.pair_edges[b] {
-            let other_idx = other as usize;
-            let seat_other_old = self.seat_of[other_idx] as u16;
-            let seat_other_new = if other_idx == a {
-                seat_a_new
+        }
+        let a = self.by_seat[i];
+        let b = self.by_seat[j];
+        let seat_i = i as u16;
+        let seat_j = j as u16;
+
+        // swap 後の座席
+        let seat_after = |student: u16| {
+            if student == a {
+                seat_j
+            } else if student == b {
+                seat_i
             } else {
-                seat_other_old
-            };
-            let old = problem.distance(seat_b_old, seat_other_old) as f32;
-            let new = problem.distance(seat_b_new, seat_other_new) as f32;
-            delta += w * (new - old);
+                self.seat_of[student as usize]
+            }
+        };
+
+        if problem.objective != Objective::Sum {
+            return self.delta_objective(problem, a, b, seat_i, seat_j, seat_after);
+        }
+
+        let mut delta = 0.0f32;
+        let mut violations_delta = 0i32;
+        let mut moves_delta = 0i32;
+
+        // 移動する学生 (A: i -> j, B: j -> i) のみ再計算
+        for (student, old_seat, new_seat) in [(a, seat_i, seat_j), (b, seat_j, seat_i)] {
+            if student == EMPTY_SEAT {
+                continue;
+            }
+            let student = student as usize;
+
+            // 過去の座席
+            delta += problem.history_seat_cost(student as u16, new_seat)
+                - problem.history_seat_cost(student as u16, old_seat);
+
+            // 過去の隣
+            if let Some(edges) = problem.history_pairs.get(student) {
+                for &(other, penalty) in edges {
+                    let old = problem.are_neighbours(old_seat, self.seat_of[other as usize]);
+                    let new = problem.are_neighbours(new_seat, seat_after(other));
+                    delta += penalty * (new as i32 - old as i32) as f32;
+                }
+            }
+
+            // 分離条件
+            if let Some(rules) = problem.separations.get(student) {
+                for &(other, rule) in rules {
+                    let old =
+                        problem.separation_violated(rule, old_seat, self.seat_of[other as usize]);
+                    let new = problem.separation_violated(rule, new_seat, seat_after(other));
+                    violations_delta += new as i32 - old as i32;
+                }
+            }
+
+            // 安定化項
+            moves_delta += problem.has_moved(student as u16, new_seat) as i32
+                - problem.has_moved(student as u16, old_seat) as i32;
+        }
+        if moves_delta != 0 {
+            let moves = (self.moves as i32 + moves_delta) as u32;
+            violations_delta +=
+                problem.excess_moves(moves) as i32 - problem.excess_moves(self.moves) as i32;
+            delta += problem.move_weight * moves_delta as f32;
+        }
+        if violations_delta != 0 {
+            delta += problem.separation_penalty * violations_delta as f32;
+        }
+        SwapDelta {
+            violations: violations_delta,
+            cost: delta + self.delta_terms(problem, seat_i, seat_j, a, b, true),
+            tail: 0.0,
+        }
+    }
+
+    /// コスト項の swap 差分（`include_per_student` が偽なら、学生に帰属しない項だけを含める）
+    fn delta_terms<D: DistanceFn>(
+        &self,
+        problem: &Problem<D>,
+        seat_i: u16,
+        seat_j: u16,
+        a: u16,
+        b: u16,
+        include_per_student: bool,
+    ) -> f32 {
+        let swap = Swap::new(&self.seat_of, seat_i, seat_j, a, b);
+        let mut delta = 0.0f32;
+        for (weight, term) in &problem.cost_terms {
+            if include_per_student || !term.per_student() {
+                delta += weight * term.delta_swap(problem, &swap);
+            }
         }
         delta
     }
 
-    /// 2 座席 swap を適用し cost を更新（delta を受け取る）
-    pub(crate) fn apply_swap(&mut self, i: usize, j: usize, delta: f32) {
+    /// `Sum` 以外の目的関数での swap の差分コスト
+    ///
+    /// `soft_cost` が変わりうる学生だけを再計算し、上位コストは `ranking` から求める。
+    fn delta_objective<D: DistanceFn>(
+        &self,
+        problem: &Problem<D>,
+        a: u16,
+        b: u16,
+        seat_i: u16,
+        seat_j: u16,
+        seat_after: impl Fn(u16) -> u16,
+    ) -> SwapDelta {
+        let affected = Self::affected_students(problem, a, b);
+        let old: Vec<f32> = affected
+            .iter()
+            .map(|&student| self.soft_costs[student as usize])
+            .collect();
+        let new: Vec<f32> = affected
+            .iter()
+            .map(|&student| Self::soft_cost(problem, student, &seat_after))
+            .collect();
+        let mut sum_delta = 0.0f32;
+        for (&old, &new) in old.iter().zip(&new) {
+            sum_delta += new - old;
+        }
+        let depth = problem.objective.depth();
+        let old_top = self.ranking.top_after(depth, &[], &[]);
+        let new_top = self.ranking.top_after(depth, &old, &new);
+        let mut delta = problem.objective.scalarize(sum_delta, &new_top)
+            - problem.objective.scalarize(0.0, &old_top);
+        let tail = match problem.objective {
+            Objective::LexMinMax { .. } => new_top
+                .iter()
+                .zip(&old_top)
+                .skip(1)
+                .map(|(&new, &old)| new - old)
+                .find(|&diff| diff != 0.0)
+                .unwrap_or(0.0),
+            _ => 0.0,
+        };
+
+        // 絶対条件の違反数の変化
+        let mut violations_delta = 0i32;
+        let mut moves_delta = 0i32;
+        for (student, old_seat, new_seat) in [(a, seat_i, seat_j), (b, seat_j, seat_i)] {
+            if student == EMPTY_SEAT {
+                continue;
+            }
+            if let Some(rules) = problem.separations.get(student as usize) {
+                for &(other, rule) in rules {
+                    let old =
+                        problem.separation_violated(rule, old_seat, self.seat_of[other as usize]);
+                    let new = problem.separation_violated(rule, new_seat, seat_after(other));
+                    violations_delta += new as i32 - old as i32;
+                }
+            }
+            moves_delta += problem.has_moved(student, new_seat) as i32
+                - problem.has_moved(student, old_seat) as i32;
+        }
+        if moves_delta != 0 {
+            let moves = (self.moves as i32 + moves_delta) as u32;
+            violations_delta +=
+                problem.excess_moves(moves) as i32 - problem.excess_moves(self.moves) as i32;
+        }
+        if violations_delta != 0 {
+            delta += problem.separation_penalty * violations_delta as f32;
+        }
+        // 学生ごとに分けられるコスト項は soft_cost に含まれている
+        SwapDelta {
+            violations: violations_delta,
+            cost: delta + self.delta_terms(problem, seat_i, seat_j, a, b, false),
+            tail,
+        }
+    }
+
+    /// 2 座席 swap が禁止席の制約を破らないかを返します。
+    #[inline]
+    pub(crate) fn swap_allowed<D: DistanceFn>(
+        &self,
+        problem: &Problem<D>,
+        i: usize,
+        j: usize,
+    ) -> bool {
+        if problem.forbidden.is_empty() {
+            return true;
+        }
+        let a = self.by_seat[i];
+        let b = self.by_seat[j];
+        (a == EMPTY_SEAT || !problem.is_forbidden(a, j as u16))
+            && (b == EMPTY_SEAT || !problem.is_forbidden(b, i as u16))
+    }
+
+    /// 2 座席 swap を適用し cost・違反数・移動人数（と学生ごとのコスト）を更新（delta を受け取る）
+    pub(crate) fn apply_swap<D: DistanceFn>(
+        &mut self,
+        problem: &Problem<D>,
+        i: usize,
+        j: usize,
+        delta: SwapDelta,
+    ) {
+        if !problem.reference_seat.is_empty() {
+            for (seat, new_seat) in [(i, j), (j, i)] {
+                let student = self.by_seat[seat];
+                if student == EMPTY_SEAT {
+                    continue;
+                }
+                let moved = problem.has_moved(student, new_seat as u16);
+                let was_moved = problem.has_moved(student, seat as u16);
+                self.moves = (self.moves as i32 + moved as i32 - was_moved as i32) as u32;
+            }
+        }
+        let a = self.by_seat[i];
+        let b = self.by_seat[j];
+        self.swap_seats(i, j);
+        self.cost += delta.cost;
+        self.violations = (self.violations as i32 + delta.violations) as u32;
+        if problem.objective != Objective::Sum {
+            for student in Self::affected_students(problem, a, b) {
+                let seat_of = &self.seat_of;
+                let new = Self::soft_cost(problem, student, |other| seat_of[other as usize]);
+                let old = std::mem::replace(&mut self.soft_costs[student as usize], new);
+                self.ranking.remove(old);
+                self.ranking.insert(new);
+            }
+            self.tail = Self::lex_tail(problem, &self.ranking);
+        }
+    }
+
+    /// 2 座席の中身を入れ替える（cost は更新しない）
+    #[inline]
+    pub(crate) fn swap_seats(&mut self, i: usize, j: usize) {
         self.by_seat.swap(i, j);
-        let a = self.by_seat[i] as usize;
-        let b = self.by_seat[j] as usize;
-        self.seat_of[a] = i as u16;
-        self.seat_of[b] = j as u16;
-        self.cost += delta;
+        let a = self.by_seat[i];
+        let b = self.by_seat[j];
+        if a != EMPTY_SEAT {
+            self.seat_of[a as usize] = i as u16;
+        }
+        if b != EMPTY_SEAT {
+            self.seat_of[b as usize] = j as u16;
+        }
+    }
+
+    /// 解の優劣を比べます（違反している絶対条件の数が少ない方が良く、同数ならコストで比べる）。
+    ///
+    /// 違反ペナルティの大きさによらず、違反の少ない解が常に優先されます。
+    /// `LexMinMax` ではコストが等しいとき 2 番目以降の上位コストを辞書式に比べます。
+    pub(crate) fn compare(&self, other: &Self) -> Ordering {
+        self.violations
+            .cmp(&other.violations)
+            .then_with(|| self.cost.total_cmp(&other.cost))
+            .then_with(|| {
+                self.tail
+                    .iter()
+                    .zip(&other.tail)
+                    .map(|(a, b)| a.total_cmp(b))
+                    .find(|order| order.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+
+    /// `other` からの評価の変化（違反数は見ない）
+    ///
+    /// コストの差が `epsilon` 以下なら、`LexMinMax` の 2 番目以降の上位コストで最初に違う値の差を返す。
+    pub(crate) fn objective_delta(&self, other: &Self, epsilon: f32) -> f32 {
+        let delta = self.cost - other.cost;
+        if delta.abs() > epsilon {
+            return delta;
+        }
+        self.tail
+            .iter()
+            .zip(&other.tail)
+            .map(|(&a, &b)| a - b)
+            .find(|&diff| diff != 0.0)
+            .unwrap_or(delta)
+    }
+
+    /// `other` より良い解か（違反数が少ないか、同数で評価が `epsilon` を超えて下がる）
+    pub(crate) fn improves_on(&self, other: &Self, epsilon: f32) -> bool {
+        self.violations < other.violations
+            || (self.violations == other.violations
+                && self.objective_delta(other, epsilon) < -epsilon)
     }
 
     /// 現在の評価コストを返します。
+    ///
+    /// 安定化項と、絶対条件に違反している場合はそのペナルティも含みます。
     pub fn cost(&self) -> f32 {
         self.cost
     }
 
+    /// 違反している絶対条件の数（分離条件の違反数 + 移動人数上限の超過人数）を返します。
+    pub fn violations(&self) -> u32 {
+        self.violations
+    }
+
+    /// 安定化項の基準配置から移動した学生数を返します（基準配置がなければ 0）。
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// すべての絶対条件（固定・禁止席・分離条件・移動人数上限）を満たしているかを返します。
+    pub fn is_feasible(&self) -> bool {
+        self.violations == 0
+    }
+
     /// `seat_id -> student_id` の割り当て配列を返します。
+    ///
+    /// 空席には [`EMPTY_SEAT`] が入ります。
     pub fn by_seat(&self) -> &[u16] {
         &self.by_seat
     }
 
+    /// 空席の `seat_id` 一覧を昇順で返します。
+    pub fn empty_seats(&self) -> Vec<u16> {
+        self.by_seat
+            .iter()
+            .enumerate()
+            .filter(|&(_, &student)| student == EMPTY_SEAT)
+            .map(|(seat, _)| seat as u16)
+            .collect()
+    }
+
     /// `student_id -> seat_id` の逆引き配列を返します。
     pub fn seat_of(&self) -> &[u16] {
         &self.seat_of
@@ -0,0 +1,123 @@
+use crate::DistanceFn;
+
+use super::{individual::Individual, problem::Problem};
+
+/// 解の満足度と公平性の指標。
+///
+/// 学生ごとのコストは `Individual::breakdown` の `total` と同じ値です。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    /// 学生ごとのコスト（index = StudentId）
+    pub student_costs: Vec<f32>,
+    /// 第一希望（`want_seats` で重みが最大の候補）の座席に座れた学生の割合
+    ///
+    /// 正の重みの希望席を持つ学生だけを分母にします（該当者がいなければ `None`）。
+    pub first_choice_rate: Option<f32>,
+    /// 希望したペア相手（`pair_graph` で正の重み）の誰かと隣になれた学生の割合
+    ///
+    /// 統合後のペアグラフに正の重みの相手がいる学生だけを分母にします（該当者がいなければ `None`）。
+    pub partner_rate: Option<f32>,
+    /// コストが最大の学生（学生がいなければ `None`）
+    pub worst_student: Option<u16>,
+    /// 最大の学生コスト（学生がいなければ 0）
+    pub worst_cost: f32,
+    /// 学生コストの平均
+    pub mean_cost: f32,
+    /// 学生コストの分散（母分散）
+    pub variance: f32,
+    /// 学生コストのジニ係数（0 = 完全に平等）
+    ///
+    /// 負のコストがある場合や平均が 0 以下の場合は定義できないため `None` です。
+    pub gini: Option<f32>,
+}
+
+impl Metrics {
+    /// 問題と解から指標を計算します。
+    ///
+    /// 座席間距離が `neighbour_distance` 以下の 2 席に座った学生同士を隣とみなします。
+    pub fn compute<D: DistanceFn>(
+        problem: &Problem<D>,
+        individual: &Individual,
+        neighbour_distance: u16,
+    ) -> Self {
+        let seat_of = individual.seat_of();
+        let student_count = seat_of.len();
+        let student_costs: Vec<f32> = (0..student_count as u16)
+            .map(|student| Individual::student_cost(problem, seat_of, student))
+            .collect();
+
+        let mut wanting = 0usize;
+        let mut first_choice = 0usize;
+        let mut pairing = 0usize;
+        let mut with_partner = 0usize;
+        for (student, &seat) in seat_of.iter().enumerate() {
+            let top = problem.want_seats[student]
+                .iter()
+                .filter(|&&(_, w)| w > 0.0)
+                .fold(None, |best: Option<(u16, f32)>, &(want, w)| match best {
+                    Some((_, best_w)) if best_w >= w => best,
+                    _ => Some((want, w)),
+                });
+            if let Some((want, _)) = top {
+                wanting += 1;
+                first_choice += (want == seat) as usize;
+            }
+
+            let mut partners = problem.pair_graph[student]
+                .iter()
+                .filter(|&&(_, w)| w > 0.0)
+                .peekable();
+            if partners.peek().is_some() {
+                pairing += 1;
+                with_partner += partners.any(|&(other, _)| {
+                    problem.distance(seat, seat_of[other as usize]) <= neighbour_distance
+                }) as usize;
+            }
+        }
+        let rate = |hit: usize, total: usize| (total > 0).then(|| hit as f32 / total as f32);
+
+        let worst = student_costs
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1));
+        let n = student_count.max(1) as f32;
+        let mean_cost = student_costs.iter().sum::<f32>() / n;
+        let variance = student_costs
+            .iter()
+            .map(|&c| (c - mean_cost) * (c - mean_cost))
+            .sum::<f32>()
+            / n;
+
+        Self {
+            first_choice_rate: rate(first_choice, wanting),
+            partner_rate: rate(with_partner, pairing),
+            worst_student: worst.map(|(student, _)| student as u16),
+            worst_cost: worst.map_or(0.0, |(_, &c)| c),
+            mean_cost,
+            variance,
+            gini: gini(&student_costs),
+            student_costs,
+        }
+    }
+}
+
+/// ジニ係数（昇順ソートによる O(n log n) の計算）
+fn gini(values: &[f32]) -> Option<f32> {
+    if values.iter().any(|&v| v < 0.0) {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let n = sorted.len() as f64;
+    let total: f64 = sorted.iter().map(|&v| v as f64).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    // G = Σ (2i - n - 1) x_i / (n Σ x)  （i は 1 始まり）
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| (2.0 * (i + 1) as f64 - n - 1.0) * v as f64)
+        .sum();
+    Some((weighted / (n * total)) as f32)
+}
@@ -4,6 +4,7 @@
 //!  * 差分計算付き 2-swap ヒルクライム
 //!  * ランダム大ジャンプ
 //!  * シミュレーテッドアニーリング確率受容
+//!
 //! のハイブリッドで高速に近似最適化する。
 //!
 //! 通常はクレート直下の再エクスポート経由で
@@ -19,17 +20,48 @@
 //!
 //! let problem = Problem::new(seats, want_seats, pair_edges);
 //! let mut ilsa = ILSA::new(&problem, 42);
-//! let _best = ilsa.solve(10);
+//! let _best = ilsa.solve(10).expect("feasible problem");
 //! ```
 
+mod assignment;
+mod bound;
+mod breakdown;
+mod builder;
+mod config;
+mod error;
+mod exact;
 mod ilsa;
 mod individual;
+mod metrics;
+mod objective;
+mod observer;
+mod parallel;
 mod problem;
 mod rng;
+mod rotation;
+mod term;
 
+pub use assignment::AssignmentSolver;
+pub use bound::GapReport;
+pub use breakdown::{CostBreakdown, PairContribution, StudentCost, WantContribution};
+pub use builder::{IdMap, ProblemBuilder};
+pub use config::{ILSAConfig, InitialTemperature};
+pub use error::{ProblemError, SolveError};
+pub use exact::{DEFAULT_EXACT_NODE_LIMIT, EXACT_MAX_SEATS, ExactSolution, ExactSolver};
 pub use ilsa::ILSA;
 pub use individual::Individual;
-pub use problem::{Problem, Seat, WeightedSeatPref, DistanceFn};
+pub use metrics::Metrics;
+pub use objective::Objective;
+pub use observer::{Control, Observer, Progress};
+pub use parallel::{DEFAULT_CHAINS, ParallelILSA};
+pub use problem::{
+    CappedDistanceFn, ChebyshevDistanceFn, DEFAULT_PREFERENCE_TABLE_LIMIT,
+    DEFAULT_SEPARATION_PENALTY, DISTANCE_TABLE_MAX_SEATS, DefaultDistanceFn, DeskPairDistanceFn,
+    DistanceFn, EMPTY_SEAT, EuclideanDistanceFn, HistoryPenalty, PairMergePolicy, Problem, Seat,
+    Separation, WeightedSeatPref,
+};
+pub use rotation::{NeighbourCoverage, RotationPlan, RotationPlanner};
+pub use term::{CostTerm, PairDistanceTerm, SeatMove, SeatPreferenceTerm, Swap};
 
 #[cfg(test)]
 mod tests;
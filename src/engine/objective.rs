@@ -0,0 +1,143 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+/// 学生ごとのコストをまとめる目的関数。
+///
+/// `Sum` 以外では、分離条件と移動人数上限の違反ペナルティは学生コストに含めず、
+/// 目的関数の値へそのまま加算されます（公平性の指標で違反が隠れないようにするため）。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Objective {
+    /// 学生コストの合計を最小化する（従来どおり）
+    #[default]
+    Sum,
+    /// 学生コストの最大値を最小化する
+    MinMax,
+    /// 大きい順に上位 `depth` 人のコストを並べたベクトルを辞書式に最小化する
+    ///
+    /// 最大コストが等しいときに限り 2 番目以降で比べます（上位 2 人が `[10.0, 9.0]` の配置は
+    /// `[10.005, 0.0]` の配置より良いと評価されます）。コストの値 (`Individual::cost`) は
+    /// `MinMax` と同じく最大コストです。
+    LexMinMax { depth: usize },
+    /// `sum_weight × 合計 + max_weight × 最大値` を最小化する
+    Weighted { sum_weight: f32, max_weight: f32 },
+}
+
+impl Objective {
+    /// 評価に必要な上位コストの人数（`Sum` は 0）
+    pub(crate) fn depth(&self) -> usize {
+        match *self {
+            Self::Sum => 0,
+            Self::MinMax | Self::Weighted { .. } => 1,
+            Self::LexMinMax { depth } => depth,
+        }
+    }
+
+    /// 学生コストの合計 `sum` と、大きい順の上位コスト `top` から目的関数の値を計算する
+    ///
+    /// `sum` について線形なので、差分 (`sum` の変化量) を渡して差分計算にも使える。
+    pub(crate) fn scalarize(&self, sum: f32, top: &[f32]) -> f32 {
+        let max = top.first().copied().unwrap_or(0.0);
+        match *self {
+            Self::Sum => sum,
+            Self::MinMax | Self::LexMinMax { .. } => max,
+            Self::Weighted {
+                sum_weight,
+                max_weight,
+            } => sum_weight * sum + max_weight * max,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            Self::Sum | Self::MinMax => true,
+            Self::LexMinMax { depth } => depth > 0,
+            Self::Weighted {
+                sum_weight,
+                max_weight,
+            } => {
+                sum_weight.is_finite()
+                    && sum_weight >= 0.0
+                    && max_weight.is_finite()
+                    && max_weight >= 0.0
+            }
+        }
+    }
+}
+
+/// `f32` を全順序で比較するためのキー
+#[derive(Debug, Clone, Copy)]
+struct CostKey(f32);
+
+impl PartialEq for CostKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CostKey {}
+
+impl PartialOrd for CostKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CostKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// 学生コストの多重集合（上位の値を差分計算で取り出すため）
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CostRanking {
+    counts: BTreeMap<CostKey, u32>,
+}
+
+impl CostRanking {
+    pub(crate) fn from_costs(costs: &[f32]) -> Self {
+        let mut ranking = Self::default();
+        for &cost in costs {
+            ranking.insert(cost);
+        }
+        ranking
+    }
+
+    pub(crate) fn insert(&mut self, cost: f32) {
+        *self.counts.entry(CostKey(cost)).or_default() += 1;
+    }
+
+    pub(crate) fn remove(&mut self, cost: f32) {
+        let key = CostKey(cost);
+        if let Some(count) = self.counts.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&key);
+            }
+        }
+    }
+
+    /// `removed` を取り除き `added` を加えた多重集合の、大きい順の上位 `k` 個を返す
+    ///
+    /// 多重集合自体は変更しない。`removed` は集合に含まれる値であること。
+    pub(crate) fn top_after(&self, k: usize, removed: &[f32], added: &[f32]) -> Vec<f32> {
+        let mut skip: Vec<f32> = removed.to_vec();
+        let mut from_set = Vec::with_capacity(k);
+        'outer: for (key, &count) in self.counts.iter().rev() {
+            for _ in 0..count {
+                if let Some(pos) = skip.iter().position(|&r| r.total_cmp(&key.0).is_eq()) {
+                    skip.swap_remove(pos);
+                    continue;
+                }
+                from_set.push(key.0);
+                if from_set.len() == k {
+                    break 'outer;
+                }
+            }
+        }
+        let mut top = from_set;
+        top.extend_from_slice(added);
+        top.sort_by(|a, b| b.total_cmp(a));
+        top.truncate(k);
+        top
+    }
+}
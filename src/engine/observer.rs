@@ -0,0 +1,39 @@
+/// 1 ジャンプごとの探索の進捗。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// 完了したジャンプ回数（1 始まり）
+    pub iteration: usize,
+    /// 現在の温度
+    pub temperature: f32,
+    /// 現在解のコスト
+    pub current_cost: f32,
+    /// これまでの最良コスト
+    pub best_cost: f32,
+    /// 最良解が更新されていないジャンプ回数
+    pub stall: usize,
+    /// 候補プールの件数
+    pub pool_size: usize,
+}
+
+/// 探索を続けるか打ち切るかの指示。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// 探索を続ける
+    Continue,
+    /// 探索を打ち切り、それまでの最良解を返す
+    Stop,
+}
+
+/// 探索の進捗を受け取るオブザーバー。
+///
+/// `FnMut(&Progress) -> Control` のクロージャもそのまま渡せます。
+pub trait Observer {
+    /// ジャンプ 1 回ごとに呼ばれます。
+    fn on_iteration(&mut self, progress: &Progress) -> Control;
+}
+
+impl<F: FnMut(&Progress) -> Control> Observer for F {
+    fn on_iteration(&mut self, progress: &Progress) -> Control {
+        self(progress)
+    }
+}
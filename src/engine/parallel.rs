@@ -0,0 +1,178 @@
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use log::info;
+
+use crate::DistanceFn;
+
+use super::{
+    config::ILSAConfig,
+    error::SolveError,
+    ilsa::ILSA,
+    individual::Individual,
+    problem::{DefaultDistanceFn, Problem},
+    rng::SimpleRng,
+};
+
+/// 既定の独立探索（チェーン）本数。
+pub const DEFAULT_CHAINS: usize = 8;
+
+/// 複数の ILSA チェーンを別スレッドで独立に走らせるマルチスタートソルバー。
+///
+/// 各チェーンのシードはマスターシードから導出され、結果はチェーン番号順に
+/// 1 つの候補プールへ統合されます。そのため、マスターシードとチェーン本数が同じなら
+/// スレッド数やスケジューリングによらず同じ結果になります
+/// （制限時間・キャンセルで打ち切った場合を除く）。
+pub struct ParallelILSA<'p, D: DistanceFn = DefaultDistanceFn> {
+    problem: &'p Problem<D>,
+    seed: u64,
+    config: ILSAConfig,
+    chains: usize,
+    threads: usize,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl<'p, D: DistanceFn + Sync> ParallelILSA<'p, D> {
+    /// 新規インスタンスを作成します。
+    ///
+    /// `seed = 0` の場合はシステム乱数からチェーンのシードを導出します。
+    /// チェーン本数は `DEFAULT_CHAINS`、スレッド数は利用可能なコア数です。
+    pub fn new(problem: &'p Problem<D>, seed: u64) -> Self {
+        Self::with_config(problem, seed, ILSAConfig::default())
+    }
+
+    /// 探索パラメータを指定して新規インスタンスを作成します（全チェーン共通）。
+    pub fn with_config(problem: &'p Problem<D>, seed: u64, config: ILSAConfig) -> Self {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Self {
+            problem,
+            seed,
+            config,
+            chains: DEFAULT_CHAINS,
+            threads,
+            cancel: None,
+        }
+    }
+
+    /// 独立探索の本数を設定します（最低 1）。
+    pub fn with_chains(mut self, chains: usize) -> Self {
+        self.chains = chains.max(1);
+        self
+    }
+
+    /// 使用するスレッド数を設定します（最低 1）。結果には影響しません。
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// 協調的キャンセル用のフラグを設定します（全チェーン共通）。
+    pub fn with_cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// 全チェーンの中で最良の候補 1 件を返します。
+    pub fn solve(&self, budget: usize) -> Result<Individual, SolveError> {
+        Ok(self
+            .solve_candidates(budget, 1)?
+            .into_iter()
+            .next()
+            .expect("solve_candidates always returns at least one candidate"))
+    }
+
+    /// 全チェーンの候補プールを統合し、複数候補を返します。
+    /// - `budget` – チェーン 1 本あたりの最大ジャンプ回数
+    /// - `max_candidates` – 返す候補数（必ずこの件数を返す）
+    pub fn solve_candidates(
+        &self,
+        budget: usize,
+        max_candidates: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
+        self.problem.validate()?;
+        let candidate_limit = max_candidates.max(1);
+        let seeds = self.chain_seeds();
+        let threads = self.threads.min(seeds.len());
+
+        info!(
+            "ParallelILSA start: chains={}, threads={}, budget={}, candidate_limit={}",
+            seeds.len(),
+            threads,
+            budget,
+            candidate_limit
+        );
+
+        // 空いたスレッドから順にチェーンを取り、結果はチェーン番号の位置へ格納する
+        let next_chain = AtomicUsize::new(0);
+        let mut results: Vec<Option<Result<Vec<Individual>, SolveError>>> =
+            (0..seeds.len()).map(|_| None).collect();
+        thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let chain = next_chain.fetch_add(1, Ordering::Relaxed);
+                            let Some(&seed) = seeds.get(chain) else {
+                                break;
+                            };
+                            done.push((chain, self.run_chain(seed, budget, candidate_limit)));
+                        }
+                        done
+                    })
+                })
+                .collect::<Vec<_>>();
+            for worker in workers {
+                for (chain, result) in worker.join().expect("ILSA chain panicked") {
+                    results[chain] = Some(result);
+                }
+            }
+        });
+
+        // チェーン番号順に同じ候補プール処理へ流して統合する
+        let history_limit = ILSA::<D>::history_limit(&self.config, candidate_limit);
+        let mut merged = Vec::new();
+        let mut seen = HashSet::<Vec<u16>>::new();
+        for result in results {
+            let history = result.expect("every chain is executed")?;
+            for candidate in &history {
+                ILSA::<D>::push_history_candidate(&mut merged, &mut seen, candidate, history_limit);
+            }
+        }
+        ILSA::<D>::retain_feasible(&mut merged)?;
+        let candidates = ILSA::<D>::select_top_n_history_ordered(&merged, candidate_limit);
+
+        info!(
+            "ParallelILSA done: best_cost={:.3}, returned_candidates={}",
+            ILSA::<D>::min_cost(&candidates),
+            candidates.len()
+        );
+        Ok(candidates)
+    }
+
+    fn run_chain(
+        &self,
+        seed: u64,
+        budget: usize,
+        candidate_limit: usize,
+    ) -> Result<Vec<Individual>, SolveError> {
+        let mut ilsa = ILSA::with_config(self.problem, seed, self.config.clone());
+        if let Some(flag) = &self.cancel {
+            ilsa = ilsa.with_cancel_flag(flag.clone());
+        }
+        ilsa.search_history(None, budget, candidate_limit)
+    }
+
+    /// マスターシードから各チェーンのシード（0 以外）を導出する
+    fn chain_seeds(&self) -> Vec<u64> {
+        let mut rng = SimpleRng::new(self.seed);
+        (0..self.chains).map(|_| rng.next_u64().max(1)).collect()
+    }
+}
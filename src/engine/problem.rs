@@ -1,3 +1,14 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use super::{
+    error::ProblemError,
+    objective::Objective,
+    term::{CostTerm, PairDistanceTerm, SeatPreferenceTerm},
+};
+
 /// 座席座標。
 ///
 /// `x` が列、`y` が行を表します。
@@ -10,10 +21,84 @@ pub struct Seat {
 /// 希望座席 `(seat_id, weight)`。
 pub type WeightedSeatPref = (u16, f32);
 
+/// 空席を表す `by_seat` の値。
+///
+/// 座席数が学生数より多い場合、余った座席にはこの値が入ります。
+pub const EMPTY_SEAT: u16 = u16::MAX;
+
+/// 学生ペアを離すための絶対条件。
+///
+/// `Problem::with_separations` で `(student_a, student_b, Separation)` として指定します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separation {
+    /// 距離関数で測った座席間距離がこの値以上であること
+    MinDistance(u16),
+    /// 同じ行 (`y`) に座らないこと
+    DifferentRow,
+    /// 同じ列 (`x`) に座らないこと
+    DifferentColumn,
+}
+
+/// 非対称なペア回答（`a -> b` と `b -> a` で重みが違う、片方しかない等）の統合方法。
+///
+/// 回答がない方向は無視し、存在する回答だけを統合します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairMergePolicy {
+    /// 回答の重みを合計する
+    Sum,
+    /// 回答の重みを平均する
+    #[default]
+    Average,
+    /// 回答の重みの最小値を取る
+    Min,
+    /// 回答の重みの最大値を取る
+    Max,
+}
+
+/// 過去の席配置を繰り返したときのペナルティ設定。
+///
+/// `Problem::with_history` で履歴と一緒に指定します。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryPenalty {
+    /// 過去と同じ座席に座った場合のペナルティ
+    pub same_seat: f32,
+    /// 過去に隣だった学生と再び隣になった場合のペナルティ
+    pub same_neighbour: f32,
+    /// 1 期さかのぼるごとにペナルティへ掛ける減衰率（`1.0` なら減衰なし）
+    pub decay: f32,
+    /// 隣とみなす座席間距離の上限（距離関数で測る）
+    pub neighbour_distance: u16,
+}
+
+impl Default for HistoryPenalty {
+    fn default() -> Self {
+        Self {
+            same_seat: 1.0,
+            same_neighbour: 1.0,
+            decay: 1.0,
+            neighbour_distance: 1,
+        }
+    }
+}
+
+/// 距離テーブルを前計算する座席数の上限（これを超えると毎回 `DistanceFn` を呼ぶ）。
+///
+/// 4096 席で 32 MiB になります。
+pub const DISTANCE_TABLE_MAX_SEATS: usize = 4096;
+
+/// 希望席コストテーブルの既定の要素数上限（学生数 × 座席数）。
+///
+/// 4M 要素 (16 MiB) を超える問題ではテーブルを作らず、毎回 `want_seats` を走査します。
+pub const DEFAULT_PREFERENCE_TABLE_LIMIT: usize = 1 << 22;
+
+/// 分離条件 1 件あたりの既定ペナルティ。
+pub const DEFAULT_SEPARATION_PENALTY: f32 = 1000.0;
+
 pub trait DistanceFn {
     fn distance(&self, a: (i16, i16), b: (i16, i16)) -> u16;
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct DefaultDistanceFn;
 
 impl DistanceFn for DefaultDistanceFn {
@@ -22,36 +107,494 @@ impl DistanceFn for DefaultDistanceFn {
     }
 }
 
+/// チェビシェフ距離（斜め隣も距離 1）。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChebyshevDistanceFn;
+
+impl DistanceFn for ChebyshevDistanceFn {
+    fn distance(&self, a: (i16, i16), b: (i16, i16)) -> u16 {
+        (a.0 - b.0).unsigned_abs().max((a.1 - b.1).unsigned_abs())
+    }
+}
+
+/// ユークリッド距離を四捨五入した整数距離。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EuclideanDistanceFn;
+
+impl DistanceFn for EuclideanDistanceFn {
+    fn distance(&self, a: (i16, i16), b: (i16, i16)) -> u16 {
+        let dx = (a.0 - b.0) as f64;
+        let dy = (a.1 - b.1) as f64;
+        (dx * dx + dy * dy).sqrt().round() as u16
+    }
+}
+
+/// 2 台の机をくっつけた「机ペア」を距離 0 とみなす距離。
+///
+/// 同じ行 (`y`) で、列 `x` が `(0, 1)`, `(2, 3)`, … の組になる 2 席を同じ机ペアとみなします。
+/// それ以外の 2 席の距離は `inner` で計算します。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeskPairDistanceFn<D: DistanceFn = DefaultDistanceFn> {
+    pub inner: D,
+}
+
+impl<D: DistanceFn> DistanceFn for DeskPairDistanceFn<D> {
+    fn distance(&self, a: (i16, i16), b: (i16, i16)) -> u16 {
+        if a.1 == b.1 && a.0.div_euclid(2) == b.0.div_euclid(2) {
+            return 0;
+        }
+        self.inner.distance(a, b)
+    }
+}
+
+/// `threshold` 以下の距離を 0 とみなし、`cap` を超える距離を `cap` に切り詰める距離。
+///
+/// 「隣（斜め含む）なら満足、離れていれば一定の不満」のようなペア希望に使います
+/// （例: `CappedDistanceFn { inner: ChebyshevDistanceFn, threshold: 1, cap: 2 }`）。
+/// 妥当な既定値がないため `Default` は実装していません（`cap = 0` ではすべての距離が 0 になります）。
+#[derive(Debug, Clone, Copy)]
+pub struct CappedDistanceFn<D: DistanceFn = DefaultDistanceFn> {
+    pub inner: D,
+    pub threshold: u16,
+    pub cap: u16,
+}
+
+impl<D: DistanceFn> DistanceFn for CappedDistanceFn<D> {
+    fn distance(&self, a: (i16, i16), b: (i16, i16)) -> u16 {
+        let d = self.inner.distance(a, b);
+        if d <= self.threshold {
+            0
+        } else {
+            d.min(self.cap)
+        }
+    }
+}
+
 /// 最適化問題定義。
 ///
-/// すべてのベクタ長は座席数と整合していることを想定します。
+/// 学生数は `want_seats.len()` で決まり、座席数とは独立に指定できます。
+/// `pair_edges` の長さは学生数と一致し、学生数は座席数以下であることを想定します。
+///
+/// `pair_edges` は構築時に `PairMergePolicy` で対称なグラフへ正規化され、
+/// コスト計算には正規化後のグラフ (`pair_graph`) が使われます。
+///
+/// `seats` / `want_seats` / `pair_edges` / `distance_fn` は公開フィールドですが、
+/// 構築後に直接書き換えた場合は [`Problem::refresh`] を呼んで前計算を作り直してください。
+#[derive(Clone)]
 pub struct Problem<D: DistanceFn = DefaultDistanceFn> {
     /// 有効な座席一覧（index が SeatId になる）
     pub seats: Vec<Seat>,
     /// 生徒ごとの希望座席のリスト
     /// want_seats[student] -> [(SeatId, weight)]
     pub want_seats: Vec<Vec<WeightedSeatPref>>,
-    /// 生徒ごとの (相手, 重み f32) 隣接リスト
+    /// 生徒ごとの (相手, 重み f32) 隣接リスト（入力そのまま）
     /// pair_edges[student] -> [(other, weight)]
     pub pair_edges: Vec<Vec<WeightedSeatPref>>,
+    /// `pair_edges` を対称化・統合した隣接リスト（相手 ID 昇順、自己辺なし）
+    pub(crate) pair_graph: Vec<Vec<WeightedSeatPref>>,
+    /// `pair_graph` を作るときの統合方法
+    pub(crate) pair_merge_policy: PairMergePolicy,
     /// 距離関数
     pub distance_fn: D,
+    /// 座席間距離テーブル `dist[a * seat_count + b]`。座席数が多すぎる場合は空
+    pub(crate) distance_table: Vec<u16>,
+    /// 希望席コストテーブル `pref[student * seat_count + seat]`。無効時は空
+    pub(crate) preference_table: Vec<f32>,
+    /// 希望席コストテーブルの要素数上限
+    pub(crate) preference_table_limit: usize,
+    /// 固定割り当て (StudentId, SeatId)
+    pub(crate) pinned: Vec<(u16, u16)>,
+    /// 固定されていない座席一覧（探索で swap してよい座席）
+    pub(crate) movable_seats: Vec<u16>,
+    /// 学生ごとの禁止席（昇順）。禁止席が一つもなければ空
+    pub(crate) forbidden: Vec<Vec<u16>>,
+    /// 学生ごとの分離条件 (相手, 条件) 隣接リスト。条件が一つもなければ空
+    pub(crate) separations: Vec<Vec<(u16, Separation)>>,
+    /// 分離条件 1 件の違反あたりのペナルティ
+    pub(crate) separation_penalty: f32,
+    /// 安定化項の基準配置（学生ごとの SeatId、基準のない学生は `EMPTY_SEAT`）。未設定なら空
+    pub(crate) reference_seat: Vec<u16>,
+    /// 基準配置から移動した学生 1 人あたりのコスト
+    pub(crate) move_weight: f32,
+    /// 基準配置から移動してよい学生数の上限
+    pub(crate) max_moves: Option<usize>,
+    /// 過去の席配置 `by_seat` の一覧（古い順、入力そのまま）
+    pub(crate) history: Vec<Vec<u16>>,
+    /// 履歴のペナルティ設定
+    pub(crate) history_penalty: HistoryPenalty,
+    /// 学生ごとの過去の座席と、再び座った場合のペナルティ（SeatId 昇順）。履歴がなければ空
+    pub(crate) history_seats: Vec<Vec<(u16, f32)>>,
+    /// 過去に隣だった学生ペアと、再び隣になった場合のペナルティ（対称、相手 ID 昇順）。履歴がなければ空
+    pub(crate) history_pairs: Vec<Vec<(u16, f32)>>,
+    /// 隣とみなす座席間距離の上限
+    pub(crate) neighbour_distance: u16,
+    /// 学生ごとのコストをまとめる目的関数
+    pub(crate) objective: Objective,
+    /// コスト項と重み（登録順）。既定は組み込みの希望席・ペア距離の項
+    pub(crate) cost_terms: Vec<(f32, Arc<dyn CostTerm<D>>)>,
+    /// `cost_terms` が既定のまま（組み込みの 2 項、重み 1）か
+    pub(crate) default_cost_terms: bool,
 }
 
 impl<D: DistanceFn> Problem<D> {
     /// 距離関数を指定して問題を構築します。
+    ///
+    /// 入力は検証しません。不正な入力をエラーとして受け取りたい場合は
+    /// [`Problem::try_with_distance_fn`] を使ってください。
     pub fn with_distance_fn(
         seats: Vec<Seat>,
         want_seats: Vec<Vec<WeightedSeatPref>>,
         pair_edges: Vec<Vec<WeightedSeatPref>>,
         distance_fn: D,
     ) -> Self {
-        Self {
+        let movable_seats = (0..seats.len() as u16).collect();
+        let pair_merge_policy = PairMergePolicy::default();
+        let pair_graph = normalize_pairs(&pair_edges, pair_merge_policy);
+        let distance_table = build_distance_table(&seats, &distance_fn);
+        let mut problem = Self {
+            seats,
+            want_seats,
+            pair_edges,
+            pair_graph,
+            pair_merge_policy,
+            distance_fn,
+            distance_table,
+            preference_table: Vec::new(),
+            preference_table_limit: DEFAULT_PREFERENCE_TABLE_LIMIT,
+            pinned: Vec::new(),
+            movable_seats,
+            forbidden: Vec::new(),
+            separations: Vec::new(),
+            separation_penalty: DEFAULT_SEPARATION_PENALTY,
+            reference_seat: Vec::new(),
+            move_weight: 0.0,
+            max_moves: None,
+            history: Vec::new(),
+            history_penalty: HistoryPenalty::default(),
+            history_seats: Vec::new(),
+            history_pairs: Vec::new(),
+            neighbour_distance: 0,
+            objective: Objective::Sum,
+            cost_terms: vec![
+                (1.0, Arc::new(SeatPreferenceTerm)),
+                (1.0, Arc::new(PairDistanceTerm)),
+            ],
+            default_cost_terms: true,
+        };
+        problem.preference_table = problem.build_preference_table(DEFAULT_PREFERENCE_TABLE_LIMIT);
+        problem
+    }
+
+    /// 公開フィールド (`seats`, `want_seats`, `pair_edges`, `distance_fn`) を書き換えた後に、
+    /// 正規化したペア関係・距離テーブル・希望席コストテーブルを作り直します。
+    ///
+    /// 座席数が変わった場合、固定割り当てなどの制約は作り直されません。
+    pub fn refresh(&mut self) {
+        self.pair_graph = normalize_pairs(&self.pair_edges, self.pair_merge_policy);
+        self.distance_table = build_distance_table(&self.seats, &self.distance_fn);
+        self.preference_table = self.build_preference_table(self.preference_table_limit);
+    }
+
+    /// 距離関数を指定し、入力を検証してから問題を構築します。
+    pub fn try_with_distance_fn(
+        seats: Vec<Seat>,
+        want_seats: Vec<Vec<WeightedSeatPref>>,
+        pair_edges: Vec<Vec<WeightedSeatPref>>,
+        distance_fn: D,
+    ) -> Result<Self, ProblemError> {
+        validate_parts(&seats, &want_seats, &pair_edges)?;
+        Ok(Self::with_distance_fn(
             seats,
             want_seats,
             pair_edges,
             distance_fn,
+        ))
+    }
+
+    /// 問題定義全体を検証します。
+    ///
+    /// 座席・希望座席・ペア関係の整合性と、分離条件のペナルティを確認します。
+    pub fn validate(&self) -> Result<(), ProblemError> {
+        validate_parts(&self.seats, &self.want_seats, &self.pair_edges)?;
+        if !self.separation_penalty.is_finite() || self.separation_penalty < 0.0 {
+            return Err(ProblemError::InvalidSeparationPenalty);
+        }
+        Ok(())
+    }
+
+    /// 非対称なペア回答の統合方法を設定し、`pair_graph` を作り直します（既定値 `Average`）。
+    pub fn with_pair_merge_policy(mut self, policy: PairMergePolicy) -> Self {
+        self.pair_merge_policy = policy;
+        self.pair_graph = normalize_pairs(&self.pair_edges, policy);
+        self
+    }
+
+    /// 希望席コストテーブルの要素数上限（学生数 × 座席数）を設定し、テーブルを作り直します。
+    ///
+    /// 上限を超える場合や `0` を指定した場合はテーブルを作らず、毎回 `want_seats` を走査します。
+    /// 既定値は `DEFAULT_PREFERENCE_TABLE_LIMIT` です。
+    pub fn with_preference_table_limit(mut self, max_entries: usize) -> Self {
+        self.preference_table_limit = max_entries;
+        self.preference_table = self.build_preference_table(max_entries);
+        self
+    }
+
+    /// 学生を特定の座席に固定します（既存の固定は置き換えられます）。
+    ///
+    /// - `pins`: `(student_id, seat_id)` の一覧
+    ///
+    /// 固定された学生は初期解生成・swap・候補収集のいずれでも移動しません。
+    /// ID が範囲外の場合、同じ学生や同じ座席が複数回指定された場合はエラーを返します。
+    pub fn with_pinned_seats(
+        mut self,
+        pins: impl IntoIterator<Item = (u16, u16)>,
+    ) -> Result<Self, ProblemError> {
+        let mut locked = vec![false; self.seat_count()];
+        let mut pinned_student = vec![false; self.student_count()];
+        let mut pinned = Vec::new();
+        for (student, seat) in pins {
+            self.check_student(student)?;
+            self.check_seat(seat)?;
+            if pinned_student[student as usize] {
+                return Err(ProblemError::StudentPinnedTwice { student });
+            }
+            if locked[seat as usize] {
+                return Err(ProblemError::SeatPinnedTwice { seat });
+            }
+            pinned_student[student as usize] = true;
+            locked[seat as usize] = true;
+            pinned.push((student, seat));
+        }
+        self.pinned = pinned;
+        self.movable_seats = (0..self.seat_count() as u16)
+            .filter(|&seat| !locked[seat as usize])
+            .collect();
+        Ok(self)
+    }
+
+    /// 学生ごとの禁止席を設定します（既存の禁止席は置き換えられます）。
+    ///
+    /// - `forbidden`: `(student_id, seat_id)` の一覧
+    ///
+    /// 禁止席は重みではなく絶対条件として扱われ、ソルバーは禁止席に座らせる解を返しません。
+    /// ID が範囲外の場合はエラーを返します。
+    pub fn with_forbidden_seats(
+        mut self,
+        forbidden: impl IntoIterator<Item = (u16, u16)>,
+    ) -> Result<Self, ProblemError> {
+        let mut by_student = vec![Vec::new(); self.student_count()];
+        let mut any = false;
+        for (student, seat) in forbidden {
+            self.check_student(student)?;
+            self.check_seat(seat)?;
+            by_student[student as usize].push(seat);
+            any = true;
+        }
+        for seats in &mut by_student {
+            seats.sort_unstable();
+            seats.dedup();
+        }
+        self.forbidden = if any { by_student } else { Vec::new() };
+        Ok(self)
+    }
+
+    /// 学生ペアの分離条件を設定します（既存の条件は置き換えられます）。
+    ///
+    /// - `separations`: `(student_a, student_b, Separation)` の一覧
+    ///
+    /// 違反 1 件ごとに `separation_penalty` がコストへ加算されます。探索はペナルティの大きさによらず
+    /// 違反の少ない解を優先し、違反のない解が見つからなければ `SolveError::Infeasible` を返します。
+    /// すべての条件を満たせたかは `Individual::is_feasible` で確認できます。
+    /// ID が範囲外の場合、同じ学生同士を指定した場合はエラーを返します。
+    pub fn with_separations(
+        mut self,
+        separations: impl IntoIterator<Item = (u16, u16, Separation)>,
+    ) -> Result<Self, ProblemError> {
+        let mut by_student = vec![Vec::new(); self.student_count()];
+        let mut any = false;
+        for (a, b, rule) in separations {
+            self.check_student(a)?;
+            self.check_student(b)?;
+            if a == b {
+                return Err(ProblemError::SelfSeparation { student: a });
+            }
+            by_student[a as usize].push((b, rule));
+            by_student[b as usize].push((a, rule));
+            any = true;
         }
+        self.separations = if any { by_student } else { Vec::new() };
+        Ok(self)
+    }
+
+    /// 基準配置からの移動を抑える安定化項を設定します（既存の設定は置き換えられます）。
+    ///
+    /// - `reference`: 基準配置 `(student_id, seat_id)` の一覧（転入生など基準のない学生は省略）
+    /// - `weight`: 基準の座席から移動した学生 1 人あたりのコスト
+    /// - `max_moves`: 移動してよい学生数の上限（絶対条件）
+    ///
+    /// 上限は分離条件と同じく絶対条件で、超えた 1 人ごとに違反として数えられます。
+    /// ソルバーは違反の少ない解を優先し、上限を守れる配置が見つからなければ
+    /// `SolveError::Infeasible` を返します。
+    /// ID が範囲外の場合、同じ学生が複数回指定された場合、重みが有限の非負値でない場合はエラーを返します。
+    pub fn with_stability(
+        mut self,
+        reference: impl IntoIterator<Item = (u16, u16)>,
+        weight: f32,
+        max_moves: Option<usize>,
+    ) -> Result<Self, ProblemError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(ProblemError::InvalidStabilityWeight);
+        }
+        let mut reference_seat = vec![EMPTY_SEAT; self.student_count()];
+        for (student, seat) in reference {
+            self.check_student(student)?;
+            self.check_seat(seat)?;
+            if reference_seat[student as usize] != EMPTY_SEAT {
+                return Err(ProblemError::StudentReferencedTwice { student });
+            }
+            reference_seat[student as usize] = seat;
+        }
+        self.reference_seat = reference_seat;
+        self.move_weight = weight;
+        self.max_moves = max_moves;
+        Ok(self)
+    }
+
+    /// 過去の席配置の履歴を設定します（既存の履歴は置き換えられます）。
+    ///
+    /// - `history`: 過去の `by_seat`（`seat_id -> student_id`、空席は `EMPTY_SEAT`）を古い順に並べたもの
+    /// - `penalty`: 同じ座席・同じ隣を繰り返したときのペナルティと減衰率
+    ///
+    /// `k` 期前（直近が 0）の配置を繰り返すと、ペナルティ × `decay^k` がコストへ加算されます。
+    /// 長さが座席数と違う配置、範囲外の学生や同じ学生が複数回現れる配置、
+    /// 有限の非負値でないペナルティ、`0..=1` の範囲外の減衰率はエラーになります。
+    pub fn with_history(
+        mut self,
+        history: impl IntoIterator<Item = Vec<u16>>,
+        penalty: HistoryPenalty,
+    ) -> Result<Self, ProblemError> {
+        let valid_penalty = |p: f32| p.is_finite() && p >= 0.0;
+        if !valid_penalty(penalty.same_seat)
+            || !valid_penalty(penalty.same_neighbour)
+            || !(0.0..=1.0).contains(&penalty.decay)
+        {
+            return Err(ProblemError::InvalidHistoryPenalty);
+        }
+
+        let history: Vec<Vec<u16>> = history.into_iter().collect();
+        for (period, by_seat) in history.iter().enumerate() {
+            if by_seat.len() != self.seat_count() {
+                return Err(ProblemError::HistoryLengthMismatch {
+                    period,
+                    seats: self.seat_count(),
+                    len: by_seat.len(),
+                });
+            }
+            let mut seen = vec![false; self.student_count()];
+            for &student in by_seat.iter().filter(|&&student| student != EMPTY_SEAT) {
+                self.check_student(student)?;
+                if std::mem::replace(&mut seen[student as usize], true) {
+                    return Err(ProblemError::StudentTwiceInHistory { period, student });
+                }
+            }
+        }
+
+        let mut seats = BTreeMap::<(u16, u16), f32>::new();
+        let mut pairs = BTreeMap::<(u16, u16), f32>::new();
+        for (age, by_seat) in history.iter().rev().enumerate() {
+            let factor = penalty.decay.powi(age as i32);
+            let occupied: Vec<(u16, u16)> = by_seat
+                .iter()
+                .enumerate()
+                .filter(|&(_, &student)| student != EMPTY_SEAT)
+                .map(|(seat, &student)| (seat as u16, student))
+                .collect();
+            for &(seat, student) in &occupied {
+                *seats.entry((student, seat)).or_default() += penalty.same_seat * factor;
+            }
+            for (k, &(seat_a, a)) in occupied.iter().enumerate() {
+                for &(seat_b, b) in &occupied[(k + 1)..] {
+                    if self.distance(seat_a, seat_b) <= penalty.neighbour_distance {
+                        *pairs.entry((a.min(b), a.max(b))).or_default() +=
+                            penalty.same_neighbour * factor;
+                    }
+                }
+            }
+        }
+
+        self.history_seats = Vec::new();
+        self.history_pairs = Vec::new();
+        if seats.values().any(|&p| p != 0.0) {
+            self.history_seats = vec![Vec::new(); self.student_count()];
+            for ((student, seat), p) in seats {
+                self.history_seats[student as usize].push((seat, p));
+            }
+        }
+        if pairs.values().any(|&p| p != 0.0) {
+            self.history_pairs = vec![Vec::new(); self.student_count()];
+            for ((a, b), p) in pairs {
+                self.history_pairs[a as usize].push((b, p));
+                self.history_pairs[b as usize].push((a, p));
+            }
+            for edges in &mut self.history_pairs {
+                edges.sort_unstable_by_key(|&(other, _)| other);
+            }
+        }
+        self.neighbour_distance = penalty.neighbour_distance;
+        self.history = history;
+        self.history_penalty = penalty;
+        Ok(self)
+    }
+
+    /// 分離条件 1 件の違反あたりのペナルティを設定します（既定値 `DEFAULT_SEPARATION_PENALTY`）。
+    ///
+    /// 安定化項の移動人数上限を超えた 1 人あたりのペナルティにも使われます。
+    /// ペナルティは違反している配置のコストの表示に使われるだけで、違反の少ない解が常に優先されます。
+    pub fn with_separation_penalty(mut self, penalty: f32) -> Result<Self, ProblemError> {
+        if !penalty.is_finite() || penalty < 0.0 {
+            return Err(ProblemError::InvalidSeparationPenalty);
+        }
+        self.separation_penalty = penalty;
+        Ok(self)
+    }
+
+    /// 目的関数を設定します（既定値 `Objective::Sum`）。
+    ///
+    /// `Sum` 以外は `ILSA` / `ParallelILSA` / `RotationPlanner` で使えます。
+    /// 厳密ソルバー (`AssignmentSolver`, `ExactSolver`) は `Sum` のみに対応します。
+    pub fn with_objective(mut self, objective: Objective) -> Result<Self, ProblemError> {
+        if !objective.is_valid() {
+            return Err(ProblemError::InvalidObjective);
+        }
+        self.objective = objective;
+        Ok(self)
+    }
+
+    /// コスト項を登録します。`weight × term.evaluate(..)` が総コストに加算されます。
+    ///
+    /// 既定で登録されている組み込みの項 (`SeatPreferenceTerm`, `PairDistanceTerm`) と同じ項を
+    /// 登録すると、その分だけ重みが上乗せされます。
+    /// `AssignmentSolver` と `ExactSolver` は、コスト項が既定のままの問題しか扱えません。
+    pub fn with_cost_term(
+        mut self,
+        weight: f32,
+        term: impl CostTerm<D> + 'static,
+    ) -> Result<Self, ProblemError> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(ProblemError::InvalidCostTermWeight);
+        }
+        self.cost_terms.push((weight, Arc::new(term)));
+        self.default_cost_terms = false;
+        Ok(self)
+    }
+
+    /// 組み込みの項も含めて、登録済みのコスト項をすべて外します。
+    ///
+    /// 組み込みの項の重みを変えたい場合や外したい場合に、`with_cost_term` で登録し直す前に呼びます。
+    pub fn clear_cost_terms(mut self) -> Self {
+        self.cost_terms.clear();
+        self.default_cost_terms = false;
+        self
     }
 
     #[inline]
@@ -63,16 +606,204 @@ impl<D: DistanceFn> Problem<D> {
     #[inline]
     /// 学生数を返します。
     ///
-    /// 現在は「座席数 = 学生数」を前提にしています。
+    /// `want_seats.len()` と同じ値です。座席数より少ない場合、残りの座席は空席になります。
     pub fn student_count(&self) -> usize {
-        self.seats.len()
+        self.want_seats.len()
+    }
+
+    /// 座席一覧を返します（index が SeatId）。
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    /// 学生ごとの希望座席 `(seat_id, weight)` を返します。
+    pub fn want_seats(&self) -> &[Vec<WeightedSeatPref>] {
+        &self.want_seats
+    }
+
+    /// 入力されたままのペア関係 `(other, weight)` を返します。
+    pub fn pair_edges(&self) -> &[Vec<WeightedSeatPref>] {
+        &self.pair_edges
+    }
+
+    /// 正規化後のペア関係 `(other, weight)` を返します。
+    ///
+    /// `a -> b` があれば必ず同じ重みの `b -> a` があり、各リストは相手 ID の昇順です。
+    /// 範囲外の相手と自己辺は含みません。
+    pub fn pair_graph(&self) -> &[Vec<WeightedSeatPref>] {
+        &self.pair_graph
+    }
+
+    /// ペア回答の統合方法を返します。
+    pub fn pair_merge_policy(&self) -> PairMergePolicy {
+        self.pair_merge_policy
+    }
+
+    /// 距離関数を返します。
+    pub fn distance_fn(&self) -> &D {
+        &self.distance_fn
+    }
+
+    /// 過去の席配置の履歴（古い順）を返します。
+    pub fn history(&self) -> &[Vec<u16>] {
+        &self.history
+    }
+
+    /// 履歴のペナルティ設定を返します。
+    pub fn history_penalty(&self) -> HistoryPenalty {
+        self.history_penalty
+    }
+
+    /// 目的関数を返します。
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    /// 固定割り当て `(student_id, seat_id)` の一覧を返します。
+    pub fn pinned_seats(&self) -> &[(u16, u16)] {
+        &self.pinned
+    }
+
+    /// 座席が固定割り当てで埋まっているかを返します。
+    pub fn is_seat_locked(&self, seat: u16) -> bool {
+        self.pinned.iter().any(|&(_, s)| s == seat)
+    }
+
+    /// 学生にとって座席が禁止席かを返します。
+    #[inline]
+    pub fn is_forbidden(&self, student: u16, seat: u16) -> bool {
+        self.forbidden
+            .get(student as usize)
+            .is_some_and(|seats| seats.binary_search(&seat).is_ok())
     }
 
+    /// 学生が座席 `seat` に座ると、安定化項の基準配置から移動したことになるかを返します。
     #[inline]
-    pub(crate) fn distance(&self, a: u16, b: u16) -> u16 {
-        let sa = self.seats[a as usize];
-        let sb = self.seats[b as usize];
-        self.distance_fn.distance((sa.x, sa.y), (sb.x, sb.y))
+    pub(crate) fn has_moved(&self, student: u16, seat: u16) -> bool {
+        self.reference_seat
+            .get(student as usize)
+            .is_some_and(|&reference| reference != EMPTY_SEAT && reference != seat)
+    }
+
+    /// 移動人数 `moves` のうち、上限 `max_moves` を超えた人数を返します。
+    #[inline]
+    pub(crate) fn excess_moves(&self, moves: u32) -> u32 {
+        match self.max_moves {
+            Some(limit) => (moves as usize).saturating_sub(limit) as u32,
+            None => 0,
+        }
+    }
+
+    /// 学生が過去に座った座席 `seat` に再び座ったときのペナルティを返します（履歴がなければ 0）。
+    #[inline]
+    pub(crate) fn history_seat_cost(&self, student: u16, seat: u16) -> f32 {
+        let Some(seats) = self.history_seats.get(student as usize) else {
+            return 0.0;
+        };
+        match seats.binary_search_by_key(&seat, |&(s, _)| s) {
+            Ok(k) => seats[k].1,
+            Err(_) => 0.0,
+        }
+    }
+
+    /// 座席 `a`, `b` が履歴上の「隣」にあたるかを返します。
+    #[inline]
+    pub(crate) fn are_neighbours(&self, a: u16, b: u16) -> bool {
+        a != b && self.distance(a, b) <= self.neighbour_distance
+    }
+
+    /// ペア距離と分離条件を除いた、学生ごとに独立なコスト（希望席 + 過去の座席 + 移動の重み）を返します。
+    #[inline]
+    pub(crate) fn linear_cost(&self, student: u16, seat: u16) -> f32 {
+        let mut cost = self.preference_cost(student, seat) + self.history_seat_cost(student, seat);
+        if self.has_moved(student, seat) {
+            cost += self.move_weight;
+        }
+        cost
+    }
+
+    #[inline]
+    fn check_student(&self, student: u16) -> Result<(), ProblemError> {
+        if (student as usize) < self.student_count() {
+            Ok(())
+        } else {
+            Err(ProblemError::StudentOutOfRange { student })
+        }
+    }
+
+    #[inline]
+    fn check_seat(&self, seat: u16) -> Result<(), ProblemError> {
+        if (seat as usize) < self.seat_count() {
+            Ok(())
+        } else {
+            Err(ProblemError::SeatOutOfRange { seat })
+        }
+    }
+
+    /// 座席 `a`, `b` に座った 2 人が分離条件 `rule` に違反しているかを返します。
+    #[inline]
+    pub(crate) fn separation_violated(&self, rule: Separation, a: u16, b: u16) -> bool {
+        match rule {
+            Separation::MinDistance(min) => self.distance(a, b) < min,
+            Separation::DifferentRow => self.seats[a as usize].y == self.seats[b as usize].y,
+            Separation::DifferentColumn => self.seats[a as usize].x == self.seats[b as usize].x,
+        }
+    }
+
+    /// 学生 `student` が座席 `seat` に座ったときの希望席コストを返します。
+    ///
+    /// `want_seats[student]` の各候補について `距離 × 重み` の最小値を取ります（候補がなければ 0）。
+    /// 前計算テーブルがあれば定数時間で返します。
+    #[inline]
+    pub fn preference_cost(&self, student: u16, seat: u16) -> f32 {
+        if self.preference_table.is_empty() {
+            return self.compute_preference_cost(student, seat);
+        }
+        self.preference_table[student as usize * self.seats.len() + seat as usize]
+    }
+
+    fn compute_preference_cost(&self, student: u16, seat: u16) -> f32 {
+        let wants = &self.want_seats[student as usize];
+        if wants.is_empty() {
+            return 0.0;
+        }
+        wants
+            .iter()
+            .map(|&(ws, w)| self.distance(seat, ws) as f32 * w)
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// 全学生 × 全座席の希望席コストを前計算する（上限超過・範囲外の希望席がある場合は空）
+    fn build_preference_table(&self, max_entries: usize) -> Vec<f32> {
+        let entries = self.student_count().saturating_mul(self.seat_count());
+        let in_range = self
+            .want_seats
+            .iter()
+            .flatten()
+            .all(|&(seat, _)| (seat as usize) < self.seat_count());
+        if entries == 0 || entries > max_entries || !in_range {
+            return Vec::new();
+        }
+        let mut table = Vec::with_capacity(entries);
+        for student in 0..self.student_count() as u16 {
+            for seat in 0..self.seat_count() as u16 {
+                table.push(self.compute_preference_cost(student, seat));
+            }
+        }
+        table
+    }
+
+    /// 座席 `a`, `b` 間の距離を返します。
+    ///
+    /// 構築時に前計算した距離テーブルを参照します。
+    #[inline]
+    pub fn distance(&self, a: u16, b: u16) -> u16 {
+        if self.distance_table.is_empty() {
+            let sa = self.seats[a as usize];
+            let sb = self.seats[b as usize];
+            return self.distance_fn.distance((sa.x, sa.y), (sb.x, sb.y));
+        }
+        self.distance_table[a as usize * self.seats.len() + b as usize]
     }
 }
 
@@ -80,18 +811,146 @@ impl Problem<DefaultDistanceFn> {
     /// 問題を構築します。
     ///
     /// - `seats`: 使用可能な座席一覧
-    /// - `want_seats[student]`: 学生ごとの希望座席候補 `(seat_id, weight)`
+    /// - `want_seats[student]`: 学生ごとの希望座席候補 `(seat_id, weight)`（長さが学生数）
     /// - `pair_edges[student]`: 学生ごとの関係重み (相手ID, 重み)
+    ///
+    /// 入力は検証しません。範囲外の ID などを探索前に検出したい場合は [`Problem::try_new`] を使ってください。
     pub fn new(
         seats: Vec<Seat>,
         want_seats: Vec<Vec<WeightedSeatPref>>,
         pair_edges: Vec<Vec<WeightedSeatPref>>,
     ) -> Self {
-        Self {
-            seats,
-            want_seats,
-            pair_edges,
-            distance_fn: DefaultDistanceFn,
+        Self::with_distance_fn(seats, want_seats, pair_edges, DefaultDistanceFn)
+    }
+
+    /// 入力を検証してから問題を構築します。
+    ///
+    /// 範囲外の座席・学生 ID、長さの不一致、重複した座席座標、有限でない重み、
+    /// `u16::MAX` を超える座席数・学生数を `ProblemError` として返します。
+    pub fn try_new(
+        seats: Vec<Seat>,
+        want_seats: Vec<Vec<WeightedSeatPref>>,
+        pair_edges: Vec<Vec<WeightedSeatPref>>,
+    ) -> Result<Self, ProblemError> {
+        Self::try_with_distance_fn(seats, want_seats, pair_edges, DefaultDistanceFn)
+    }
+}
+
+/// 全座席ペアの距離を前計算する（座席数が上限を超える場合は空）
+fn build_distance_table<D: DistanceFn>(seats: &[Seat], distance_fn: &D) -> Vec<u16> {
+    let n = seats.len();
+    if n > DISTANCE_TABLE_MAX_SEATS {
+        return Vec::new();
+    }
+    let mut table = Vec::with_capacity(n * n);
+    for a in seats {
+        for b in seats {
+            table.push(distance_fn.distance((a.x, a.y), (b.x, b.y)));
+        }
+    }
+    table
+}
+
+/// ペア関係を対称化し、同じ学生ペアへの回答を `policy` で 1 つの重みにまとめる
+fn normalize_pairs(
+    pair_edges: &[Vec<WeightedSeatPref>],
+    policy: PairMergePolicy,
+) -> Vec<Vec<WeightedSeatPref>> {
+    let n = pair_edges.len();
+    // (小さい ID, 大きい ID) -> (統合中の重み, 回答数)
+    let mut merged = BTreeMap::<(u16, u16), (f32, u32)>::new();
+    for (a, edges) in pair_edges.iter().enumerate() {
+        let a = a as u16;
+        for &(b, w) in edges {
+            if b == a || b as usize >= n {
+                continue;
+            }
+            let entry = merged.entry((a.min(b), a.max(b))).or_insert((w, 0));
+            if entry.1 > 0 {
+                entry.0 = match policy {
+                    PairMergePolicy::Sum | PairMergePolicy::Average => entry.0 + w,
+                    PairMergePolicy::Min => entry.0.min(w),
+                    PairMergePolicy::Max => entry.0.max(w),
+                };
+            }
+            entry.1 += 1;
+        }
+    }
+
+    // キー昇順に push するので、各リストは相手 ID の昇順になる
+    let mut graph = vec![Vec::new(); n];
+    for ((a, b), (w, count)) in merged {
+        let w = match policy {
+            PairMergePolicy::Average => w / count as f32,
+            _ => w,
+        };
+        graph[a as usize].push((b, w));
+        graph[b as usize].push((a, w));
+    }
+    graph
+}
+
+/// 座席・希望座席・ペア関係の整合性を検証する
+fn validate_parts(
+    seats: &[Seat],
+    want_seats: &[Vec<WeightedSeatPref>],
+    pair_edges: &[Vec<WeightedSeatPref>],
+) -> Result<(), ProblemError> {
+    // u16::MAX は EMPTY_SEAT として予約しているため、ID は u16::MAX 未満に収める
+    if seats.len() > u16::MAX as usize {
+        return Err(ProblemError::TooManySeats { count: seats.len() });
+    }
+    if want_seats.len() > u16::MAX as usize {
+        return Err(ProblemError::TooManyStudents {
+            count: want_seats.len(),
+        });
+    }
+    if want_seats.len() != pair_edges.len() {
+        return Err(ProblemError::LengthMismatch {
+            want_seats: want_seats.len(),
+            pair_edges: pair_edges.len(),
+        });
+    }
+    if want_seats.len() > seats.len() {
+        return Err(ProblemError::StudentsExceedSeats {
+            students: want_seats.len(),
+            seats: seats.len(),
+        });
+    }
+
+    let mut coords = HashMap::with_capacity(seats.len());
+    for (id, seat) in seats.iter().enumerate() {
+        if let Some(&first) = coords.get(&(seat.x, seat.y)) {
+            return Err(ProblemError::DuplicateSeat {
+                first,
+                second: id as u16,
+            });
+        }
+        coords.insert((seat.x, seat.y), id as u16);
+    }
+
+    for (student, wants) in want_seats.iter().enumerate() {
+        let student = student as u16;
+        for &(seat, w) in wants {
+            if seat as usize >= seats.len() {
+                return Err(ProblemError::WantSeatOutOfRange { student, seat });
+            }
+            if !w.is_finite() {
+                return Err(ProblemError::NonFiniteWantWeight { student, seat });
+            }
+        }
+    }
+
+    for (student, edges) in pair_edges.iter().enumerate() {
+        let student = student as u16;
+        for &(other, w) in edges {
+            if other as usize >= pair_edges.len() {
+                return Err(ProblemError::PairStudentOutOfRange { student, other });
+            }
+            if !w.is_finite() {
+                return Err(ProblemError::NonFinitePairWeight { student, other });
+            }
         }
     }
+    Ok(())
 }
@@ -0,0 +1,173 @@
+use log::info;
+
+use crate::{DistanceFn, engine::problem::DefaultDistanceFn};
+
+use super::{
+    config::ILSAConfig,
+    error::SolveError,
+    ilsa::ILSA,
+    individual::Individual,
+    problem::{EMPTY_SEAT, HistoryPenalty, Problem},
+    rng::SimpleRng,
+};
+
+/// 複数期間（月ごとの席替えなど）の席配置をまとめて作るプランナー。
+///
+/// 各期間を順番に `ILSA` で解き、それまでに作った期間を履歴として次の期間に渡すことで、
+/// 同じ隣が繰り返されないようにします（貪欲法なので、期間全体での最適性は保証しません）。
+/// 各期間は `want_seats` や `pair_edges` など元の問題の条件をすべて満たすように解かれます。
+pub struct RotationPlanner<'p, D: DistanceFn = DefaultDistanceFn> {
+    problem: &'p Problem<D>,
+    seed: u64,
+    config: ILSAConfig,
+    penalty: HistoryPenalty,
+}
+
+/// `RotationPlanner` の結果。
+#[derive(Debug, Clone)]
+pub struct RotationPlan {
+    /// 期間ごとの席配置（時系列順）
+    ///
+    /// コストは元の問題で評価した値です（計画内の前の期間による履歴のペナルティは含みません）。
+    pub periods: Vec<Individual>,
+    /// 期間全体での隣の網羅状況
+    pub coverage: NeighbourCoverage,
+}
+
+/// 複数期間を通じて、各学生が何人の異なる学生と隣になったかの集計。
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighbourCoverage {
+    /// 学生ごとの、隣になった異なる学生の数
+    pub distinct_neighbours: Vec<u16>,
+    /// `distinct_neighbours` の最小値
+    pub min_distinct: u16,
+    /// `distinct_neighbours` の平均値
+    pub mean_distinct: f32,
+    /// 2 期目以降に、以前と同じ相手と隣になった回数（学生ペア単位）
+    pub repeated_pairs: usize,
+}
+
+impl NeighbourCoverage {
+    /// 期間ごとの配置から隣の網羅状況を集計します。
+    ///
+    /// 座席間距離が `neighbour_distance` 以下の 2 席に座った学生同士を隣とみなします。
+    pub fn compute<D: DistanceFn>(
+        problem: &Problem<D>,
+        periods: &[Individual],
+        neighbour_distance: u16,
+    ) -> Self {
+        let student_count = problem.student_count();
+        let mut met = vec![vec![false; student_count]; student_count];
+        let mut repeated_pairs = 0usize;
+        for period in periods {
+            let occupied: Vec<(u16, u16)> = period
+                .by_seat()
+                .iter()
+                .enumerate()
+                .filter(|&(_, &student)| student != EMPTY_SEAT)
+                .map(|(seat, &student)| (seat as u16, student))
+                .collect();
+            for (k, &(seat_a, a)) in occupied.iter().enumerate() {
+                for &(seat_b, b) in &occupied[(k + 1)..] {
+                    if problem.distance(seat_a, seat_b) > neighbour_distance {
+                        continue;
+                    }
+                    let (a, b) = (a as usize, b as usize);
+                    if met[a][b] {
+                        repeated_pairs += 1;
+                    }
+                    met[a][b] = true;
+                    met[b][a] = true;
+                }
+            }
+        }
+
+        let distinct_neighbours: Vec<u16> = met
+            .iter()
+            .map(|row| row.iter().filter(|&&m| m).count() as u16)
+            .collect();
+        let min_distinct = distinct_neighbours.iter().copied().min().unwrap_or(0);
+        let mean_distinct = if student_count == 0 {
+            0.0
+        } else {
+            distinct_neighbours.iter().map(|&n| n as f32).sum::<f32>() / student_count as f32
+        };
+        Self {
+            distinct_neighbours,
+            min_distinct,
+            mean_distinct,
+            repeated_pairs,
+        }
+    }
+}
+
+impl<'p, D: DistanceFn + Clone> RotationPlanner<'p, D> {
+    /// 新規インスタンスを作成します。
+    ///
+    /// `seed = 0` の場合はシステム乱数から各期間のシードを導出します。
+    /// 履歴のペナルティは、同じ座席には 0、同じ隣には 1.0（減衰なし、距離 1 以内を隣とみなす）です。
+    pub fn new(problem: &'p Problem<D>, seed: u64) -> Self {
+        Self {
+            problem,
+            seed,
+            config: ILSAConfig::default(),
+            penalty: HistoryPenalty {
+                same_seat: 0.0,
+                ..HistoryPenalty::default()
+            },
+        }
+    }
+
+    /// 各期間の探索パラメータを設定します。
+    pub fn with_config(mut self, config: ILSAConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 履歴のペナルティ設定を変更します。
+    ///
+    /// 問題にすでに設定されている履歴 (`Problem::with_history`) にも、このペナルティが使われます。
+    pub fn with_penalty(mut self, penalty: HistoryPenalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    /// `periods` 期間分の席配置を作ります。
+    /// - `budget` – 各期間の大ジャンプ回数
+    pub fn plan(&self, periods: usize, budget: usize) -> Result<RotationPlan, SolveError> {
+        let mut history = self.problem.history().to_vec();
+        let mut plans: Vec<Individual> = Vec::with_capacity(periods);
+        let mut rng = SimpleRng::new(self.seed);
+        for period in 0..periods {
+            let problem = self
+                .problem
+                .clone()
+                .with_history(history.clone(), self.penalty)?;
+            let seed = rng.next_u64().max(1);
+            let best = ILSA::with_config(&problem, seed, self.config.clone()).solve(budget)?;
+            info!(
+                "rotation period {} done: cost={:.3}",
+                period + 1,
+                best.cost()
+            );
+            history.push(best.by_seat().to_vec());
+            // 元の問題で評価し直す（この計画で追加した期間の履歴は含まないが、
+            // 元の問題に設定済みの履歴は、元の問題のペナルティ設定のまま含む）
+            plans.push(Individual::from_assignment(
+                self.problem,
+                best.by_seat.clone(),
+            ));
+        }
+
+        let coverage =
+            NeighbourCoverage::compute(self.problem, &plans, self.penalty.neighbour_distance);
+        info!(
+            "rotation plan done: periods={}, min_distinct={}, mean_distinct={:.2}, repeated_pairs={}",
+            periods, coverage.min_distinct, coverage.mean_distinct, coverage.repeated_pairs
+        );
+        Ok(RotationPlan {
+            periods: plans,
+            coverage,
+        })
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{DistanceFn, engine::problem::DefaultDistanceFn};
+
+use super::{
+    bound::distance_range,
+    problem::{EMPTY_SEAT, Problem},
+};
+
+/// 総コストを構成するコスト項。
+///
+/// `Problem` は重み付きのコスト項の一覧を持ち、既定では組み込みの希望席 (`SeatPreferenceTerm`) と
+/// ペア距離 (`PairDistanceTerm`) が重み 1 で登録されています。`Problem::with_cost_term` で追加の項を
+/// 登録すると `weight × evaluate` が総コストに加算されます。学校ごとの独自の条件を、
+/// `Individual` に手を入れずに追加するための拡張点です。
+/// 分離条件・安定化項・履歴はコスト項ではなく、`Problem` の設定として扱われます。
+pub trait CostTerm<D: DistanceFn = DefaultDistanceFn>: Send + Sync {
+    /// 配置全体のコストを返します（`seat_of` は index = StudentId, value = SeatId）。
+    fn evaluate(&self, problem: &Problem<D>, seat_of: &[u16]) -> f32;
+
+    /// 2 座席 swap によるコストの差分を返します。
+    ///
+    /// 探索で swap を評価するたびに呼ばれるため、`evaluate` で配置全体を評価し直さず、
+    /// 移動する学生に関係する部分だけを計算してください。
+    fn delta_swap(&self, problem: &Problem<D>, swap: &Swap<'_>) -> f32;
+
+    /// 学生ごとに分けて帰属させられる項なら `true` を返します（既定は `false`）。
+    ///
+    /// `true` の項は `student_cost` で学生ごとの負担分を返し、内訳 (`Individual::breakdown`) の
+    /// 学生ごとのコストと、`Sum` 以外の目的関数の学生コストに含まれます。
+    /// `false` の項は特定の学生に帰属せず、目的関数の値にそのまま加算されます。
+    fn per_student(&self) -> bool {
+        false
+    }
+
+    /// 学生 `student` の負担分を返します（`per_student` が `true` の項だけ呼ばれます）。
+    ///
+    /// `seat_of` は学生 ID から座席を返す関数です。全学生の負担分の合計は `evaluate` と一致させ、
+    /// 負担分は本人か、`Problem::pair_graph` で隣接する学生が動いたときだけ変わるようにしてください。
+    fn student_cost(
+        &self,
+        _problem: &Problem<D>,
+        _student: u16,
+        _seat_of: &dyn Fn(u16) -> u16,
+    ) -> f32 {
+        0.0
+    }
+
+    /// 取り得るコストの下界を返します（分からなければ `None`）。
+    ///
+    /// 1 つでも `None` の項があると `Problem::lower_bound` は
+    /// `SolveError::UnsupportedCostTerm` を返します。
+    fn lower_bound(&self, _problem: &Problem<D>) -> Option<f32> {
+        None
+    }
+}
+
+/// swap で移動する学生 1 人。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatMove {
+    pub student: u16,
+    /// 移動前の座席
+    pub from: u16,
+    /// 移動後の座席
+    pub to: u16,
+}
+
+/// `CostTerm::delta_swap` に渡す、2 座席 swap の内容。
+///
+/// 片方が空席の場合、移動する学生は 1 人です。
+#[derive(Debug, Clone, Copy)]
+pub struct Swap<'a> {
+    seat_of: &'a [u16],
+    moved: [SeatMove; 2],
+    len: usize,
+}
+
+impl<'a> Swap<'a> {
+    /// 座席 `i`, `j` の中身（空席は `EMPTY_SEAT`）を入れ替える swap を作る
+    pub(crate) fn new(seat_of: &'a [u16], i: u16, j: u16, a: u16, b: u16) -> Self {
+        let mut moved = [SeatMove {
+            student: EMPTY_SEAT,
+            from: i,
+            to: j,
+        }; 2];
+        let mut len = 0;
+        for (student, from, to) in [(a, i, j), (b, j, i)] {
+            if student != EMPTY_SEAT {
+                moved[len] = SeatMove { student, from, to };
+                len += 1;
+            }
+        }
+        Self {
+            seat_of,
+            moved,
+            len,
+        }
+    }
+
+    /// 移動する学生（1 人または 2 人）
+    pub fn moved(&self) -> &[SeatMove] {
+        &self.moved[..self.len]
+    }
+
+    /// swap 前の座席
+    pub fn seat_before(&self, student: u16) -> u16 {
+        self.seat_of[student as usize]
+    }
+
+    /// swap 後の座席
+    pub fn seat_after(&self, student: u16) -> u16 {
+        self.moved()
+            .iter()
+            .find(|m| m.student == student)
+            .map_or(self.seat_of[student as usize], |m| m.to)
+    }
+}
+
+/// 希望席のコスト（`want_seats` の中で最も近い希望席との距離 × 重み）。
+///
+/// `Problem` に既定で重み 1 で登録されています。`with_cost_term` で重ねて登録すると希望席の重みを上乗せでき、
+/// `clear_cost_terms` の後に登録し直すと重みを変えられます。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SeatPreferenceTerm;
+
+impl<D: DistanceFn> CostTerm<D> for SeatPreferenceTerm {
+    fn evaluate(&self, problem: &Problem<D>, seat_of: &[u16]) -> f32 {
+        let mut cost = 0.0f32;
+        for (student, &seat) in seat_of.iter().enumerate() {
+            cost += problem.preference_cost(student as u16, seat);
+        }
+        cost
+    }
+
+    fn delta_swap(&self, problem: &Problem<D>, swap: &Swap<'_>) -> f32 {
+        let mut delta = 0.0f32;
+        for m in swap.moved() {
+            delta += problem.preference_cost(m.student, m.to)
+                - problem.preference_cost(m.student, m.from);
+        }
+        delta
+    }
+
+    fn per_student(&self) -> bool {
+        true
+    }
+
+    fn student_cost(
+        &self,
+        problem: &Problem<D>,
+        student: u16,
+        seat_of: &dyn Fn(u16) -> u16,
+    ) -> f32 {
+        problem.preference_cost(student, seat_of(student))
+    }
+
+    fn lower_bound(&self, problem: &Problem<D>) -> Option<f32> {
+        let mut bound = 0.0f32;
+        for student in 0..problem.student_count() as u16 {
+            bound += (0..problem.seat_count() as u16)
+                .map(|seat| problem.preference_cost(student, seat))
+                .fold(f32::INFINITY, f32::min);
+        }
+        Some(bound)
+    }
+}
+
+/// ペア距離のコスト（`pair_graph` の各ペアについて距離 × 重み）。
+///
+/// `Problem` に既定で重み 1 で登録されています。各ペアのコストは 2 人で半分ずつ負担します。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PairDistanceTerm;
+
+impl<D: DistanceFn> CostTerm<D> for PairDistanceTerm {
+    fn evaluate(&self, problem: &Problem<D>, seat_of: &[u16]) -> f32 {
+        let mut cost = 0.0f32;
+        for (a, edges) in problem.pair_graph.iter().enumerate() {
+            for &(b, w) in edges {
+                if a as u16 >= b {
+                    continue;
+                }
+                cost += w * problem.distance(seat_of[a], seat_of[b as usize]) as f32;
+            }
+        }
+        cost
+    }
+
+    fn delta_swap(&self, problem: &Problem<D>, swap: &Swap<'_>) -> f32 {
+        let moved = swap.moved();
+        let mut delta = 0.0f32;
+        for (k, m) in moved.iter().enumerate() {
+            for &(other, w) in &problem.pair_graph[m.student as usize] {
+                // 動く 2 人同士のペアは 1 回だけ数える
+                if moved[..k].iter().any(|earlier| earlier.student == other) {
+                    continue;
+                }
+                let old = problem.distance(m.from, swap.seat_before(other)) as f32;
+                let new = problem.distance(m.to, swap.seat_after(other)) as f32;
+                delta += w * (new - old);
+            }
+        }
+        delta
+    }
+
+    fn per_student(&self) -> bool {
+        true
+    }
+
+    fn student_cost(
+        &self,
+        problem: &Problem<D>,
+        student: u16,
+        seat_of: &dyn Fn(u16) -> u16,
+    ) -> f32 {
+        let seat = seat_of(student);
+        let mut cost = 0.0f32;
+        for &(other, w) in &problem.pair_graph[student as usize] {
+            cost += 0.5 * w * problem.distance(seat, seat_of(other)) as f32;
+        }
+        cost
+    }
+
+    fn lower_bound(&self, problem: &Problem<D>) -> Option<f32> {
+        let seats: Vec<u16> = (0..problem.seat_count() as u16).collect();
+        let (min_distance, max_distance) = distance_range(problem, &seats);
+        let mut bound = 0.0f32;
+        for (a, edges) in problem.pair_graph.iter().enumerate() {
+            for &(b, w) in edges {
+                if a as u16 >= b {
+                    continue;
+                }
+                bound += w * if w >= 0.0 { min_distance } else { max_distance };
+            }
+        }
+        Some(bound)
+    }
+}
@@ -1,6 +1,17 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{Arc, atomic::AtomicBool},
+    time::{Duration, Instant},
+};
 
-use super::{ILSA, Problem, Seat};
+use super::{
+    AssignmentSolver, CappedDistanceFn, ChebyshevDistanceFn, Control, CostTerm,
+    DEFAULT_SEPARATION_PENALTY, DefaultDistanceFn, DeskPairDistanceFn, DistanceFn, EMPTY_SEAT,
+    EXACT_MAX_SEATS, EuclideanDistanceFn, ExactSolver, GapReport, HistoryPenalty, ILSA, ILSAConfig,
+    Individual, InitialTemperature, Metrics, NeighbourCoverage, Objective, PairDistanceTerm,
+    PairMergePolicy, ParallelILSA, Problem, ProblemBuilder, ProblemError, Progress,
+    RotationPlanner, Seat, SeatPreferenceTerm, Separation, SolveError, Swap, rng::SimpleRng,
+};
 
 fn sample_problem() -> Problem {
     let seats = vec![
@@ -31,7 +42,7 @@ fn sample_problem() -> Problem {
 fn solve_returns_valid_permutation() {
     let problem = sample_problem();
     let mut ilsa = ILSA::new(&problem, 42);
-    let best = ilsa.solve(50);
+    let best = ilsa.solve(50).unwrap();
 
     assert_eq!(best.by_seat().len(), problem.seat_count());
     assert_eq!(best.seat_of().len(), problem.student_count());
@@ -46,10 +57,10 @@ fn non_zero_seed_is_deterministic() {
     let problem = sample_problem();
 
     let mut ilsa_a = ILSA::new(&problem, 12345);
-    let best_a = ilsa_a.solve(40);
+    let best_a = ilsa_a.solve(40).unwrap();
 
     let mut ilsa_b = ILSA::new(&problem, 12345);
-    let best_b = ilsa_b.solve(40);
+    let best_b = ilsa_b.solve(40).unwrap();
 
     assert_eq!(best_a.by_seat(), best_b.by_seat());
     assert_eq!(best_a.seat_of(), best_b.seat_of());
@@ -60,7 +71,7 @@ fn non_zero_seed_is_deterministic() {
 fn zero_budget_still_returns_solution() {
     let problem = sample_problem();
     let mut ilsa = ILSA::new(&problem, 99);
-    let best = ilsa.solve(0);
+    let best = ilsa.solve(0).unwrap();
 
     assert_eq!(best.by_seat().len(), problem.student_count());
     assert!(best.cost().is_finite());
@@ -84,7 +95,7 @@ fn symmetric_problem() -> Problem {
 fn solve_candidates_prefers_unique_candidates() {
     let problem = symmetric_problem();
     let mut ilsa = ILSA::new(&problem, 7);
-    let candidates = ilsa.solve_candidates(40, 3);
+    let candidates = ilsa.solve_candidates(40, 3).unwrap();
 
     assert_eq!(candidates.len(), 3);
 
@@ -101,7 +112,7 @@ fn solve_candidates_always_returns_requested_count() {
     );
 
     let mut ilsa = ILSA::new(&problem, 1);
-    let candidates = ilsa.solve_candidates(10, 5);
+    let candidates = ilsa.solve_candidates(10, 5).unwrap();
 
     assert_eq!(candidates.len(), 5);
     assert!(candidates.iter().all(|c| c.by_seat() == [0]));
@@ -111,3 +122,1554 @@ fn solve_candidates_always_returns_requested_count() {
             .all(|w| w[0].cost() <= w[1].cost() + 1e-6)
     );
 }
+
+/// ランダムな swap 列で差分コストと厳密コストが一致することを確認する
+fn assert_delta_consistent<D: DistanceFn>(problem: &Problem<D>, seed: u64) {
+    let mut rng = SimpleRng::new(seed);
+    let mut ind = Individual::new_random(problem, &mut rng).unwrap();
+    let n = problem.seat_count();
+    for _ in 0..200 {
+        let i = rng.gen_range(0..n);
+        let j = rng.gen_range(0..n);
+        let delta = ind.delta_swap_cost(problem, i, j);
+        if !delta.cost.is_finite() {
+            continue;
+        }
+        let tail_before = ind.tail.clone();
+        ind.apply_swap(problem, i, j, delta);
+        assert_eq!(
+            ind.violations(),
+            Individual::count_violations(problem, &ind.seat_of)
+        );
+        let tail_delta = ind
+            .tail
+            .iter()
+            .zip(&tail_before)
+            .map(|(&new, &old)| new - old)
+            .find(|&diff| diff != 0.0)
+            .unwrap_or(0.0);
+        assert_eq!(delta.tail, tail_delta);
+        let mut synced = ind.clone();
+        synced.resync(problem);
+        assert_eq!(ind.tail, synced.tail);
+        let exact = Individual::calc_cost(problem, &ind.seat_of);
+        assert!(
+            (ind.cost() - exact).abs() < 1e-3,
+            "delta drift: incremental={} exact={}",
+            ind.cost(),
+            exact
+        );
+    }
+}
+
+fn vacant_problem() -> Problem {
+    let seats = (0..6).map(|i| Seat { x: i % 3, y: i / 3 }).collect();
+
+    // 6 席に 4 人
+    let want_seats = vec![vec![(5, 1.0)], vec![(0, 1.0)], vec![], vec![(2, 0.5)]];
+    let pair_edges = vec![
+        vec![(2, 1.0)],
+        vec![(3, 0.8)],
+        vec![(0, 1.0)],
+        vec![(1, 0.8)],
+    ];
+
+    Problem::new(seats, want_seats, pair_edges)
+}
+
+#[test]
+fn empty_seats_are_reported() {
+    let problem = vacant_problem();
+    let mut ilsa = ILSA::new(&problem, 3);
+    let best = ilsa.solve(30).unwrap();
+
+    assert_eq!(problem.student_count(), 4);
+    assert_eq!(best.by_seat().len(), problem.seat_count());
+    assert_eq!(best.seat_of().len(), problem.student_count());
+
+    let empty = best.empty_seats();
+    assert_eq!(empty.len(), 2);
+    assert!(
+        empty
+            .iter()
+            .all(|&s| best.by_seat()[s as usize] == EMPTY_SEAT)
+    );
+
+    let students: HashSet<u16> = best
+        .by_seat()
+        .iter()
+        .copied()
+        .filter(|&s| s != EMPTY_SEAT)
+        .collect();
+    assert_eq!(students.len(), problem.student_count());
+    for (student, &seat) in best.seat_of().iter().enumerate() {
+        assert_eq!(best.by_seat()[seat as usize], student as u16);
+    }
+}
+
+#[test]
+fn student_moves_into_vacant_preferred_seat() {
+    let seats = vec![
+        Seat { x: 0, y: 0 },
+        Seat { x: 1, y: 0 },
+        Seat { x: 2, y: 0 },
+    ];
+    let problem = Problem::new(seats, vec![vec![(2, 1.0)]], vec![vec![]]);
+
+    let mut ilsa = ILSA::new(&problem, 11);
+    let best = ilsa.solve(5).unwrap();
+
+    assert_eq!(best.seat_of(), [2]);
+    assert_eq!(best.empty_seats(), vec![0, 1]);
+    assert!(best.cost().abs() < 1e-6);
+}
+
+#[test]
+fn delta_matches_exact_cost_with_vacancies() {
+    assert_delta_consistent(&vacant_problem(), 5);
+    assert_delta_consistent(&sample_problem(), 6);
+}
+
+#[test]
+fn pinned_students_never_move() {
+    // 学生 0 は席 3 を強く希望しているが、席 0 に固定する
+    let problem = vacant_problem()
+        .with_pinned_seats([(0, 0), (3, 4)])
+        .unwrap();
+    assert!(problem.is_seat_locked(0));
+    assert!(!problem.is_seat_locked(5));
+
+    let mut rng = SimpleRng::new(9);
+    for _ in 0..20 {
+        let ind = Individual::new_random(&problem, &mut rng).unwrap();
+        assert_eq!(ind.seat_of()[0], 0);
+        assert_eq!(ind.seat_of()[3], 4);
+    }
+
+    let mut ilsa = ILSA::new(&problem, 21);
+    let candidates = ilsa.solve_candidates(40, 4).unwrap();
+    for candidate in &candidates {
+        assert_eq!(candidate.by_seat()[0], 0);
+        assert_eq!(candidate.by_seat()[4], 3);
+    }
+}
+
+#[test]
+fn pinning_two_students_to_one_seat_is_rejected() {
+    assert_eq!(
+        vacant_problem().with_pinned_seats([(0, 2), (1, 2)]).err(),
+        Some(ProblemError::SeatPinnedTwice { seat: 2 })
+    );
+}
+
+#[test]
+fn forbidden_seats_are_never_used() {
+    // 学生 0 は席 5 を希望しているが禁止、学生 1 は席 0 以外の前列を禁止
+    let problem = vacant_problem()
+        .with_forbidden_seats([(0, 5), (0, 4), (1, 1), (1, 2)])
+        .unwrap();
+
+    let mut ilsa = ILSA::new(&problem, 8);
+    let candidates = ilsa.solve_candidates(40, 4).unwrap();
+    for candidate in &candidates {
+        for (student, &seat) in candidate.seat_of().iter().enumerate() {
+            assert!(!problem.is_forbidden(student as u16, seat));
+        }
+    }
+    assert_delta_consistent(&problem, 13);
+}
+
+#[test]
+fn infeasible_forbidden_seats_return_error() {
+    let seats = vec![
+        Seat { x: 0, y: 0 },
+        Seat { x: 1, y: 0 },
+        Seat { x: 2, y: 0 },
+    ];
+    let problem = Problem::new(
+        seats,
+        vec![vec![], vec![], vec![]],
+        vec![vec![], vec![], vec![]],
+    )
+    .with_forbidden_seats([(0, 1), (0, 2), (1, 1), (1, 2)])
+    .unwrap();
+
+    let mut ilsa = ILSA::new(&problem, 4);
+    assert!(matches!(
+        ilsa.solve(10),
+        Err(SolveError::NoFeasibleAssignment { .. })
+    ));
+
+    let pinned = vacant_problem()
+        .with_pinned_seats([(2, 3)])
+        .unwrap()
+        .with_forbidden_seats([(2, 3)])
+        .unwrap();
+    let mut ilsa = ILSA::new(&pinned, 4);
+    assert_eq!(
+        ilsa.solve(10).unwrap_err(),
+        SolveError::PinnedToForbiddenSeat {
+            student: 2,
+            seat: 3
+        }
+    );
+}
+
+fn grid_problem() -> Problem {
+    let seats = (0..9).map(|i| Seat { x: i % 3, y: i / 3 }).collect();
+    let want_seats = (0..6).map(|_| vec![]).collect();
+    // 学生 0 と 1 は強く隣を希望、学生 2 と 3 も同様
+    let pair_edges = vec![
+        vec![(1, 5.0)],
+        vec![(0, 5.0)],
+        vec![(3, 5.0)],
+        vec![(2, 5.0)],
+        vec![(5, 1.0)],
+        vec![(4, 1.0)],
+    ];
+    Problem::new(seats, want_seats, pair_edges)
+}
+
+#[test]
+fn separations_are_enforced() {
+    let problem = grid_problem()
+        .with_separations([
+            (0, 1, Separation::MinDistance(2)),
+            (2, 3, Separation::DifferentRow),
+            (4, 5, Separation::DifferentColumn),
+        ])
+        .unwrap();
+
+    let mut ilsa = ILSA::new(&problem, 17);
+    let best = ilsa.solve(60).unwrap();
+
+    assert!(best.is_feasible());
+    assert_eq!(best.violations(), 0);
+    let seat = |student: usize| problem.seats[best.seat_of()[student] as usize];
+    let (s0, s1) = (seat(0), seat(1));
+    assert!((s0.x - s1.x).abs() + (s0.y - s1.y).abs() >= 2);
+    assert_ne!(seat(2).y, seat(3).y);
+    assert_ne!(seat(4).x, seat(5).x);
+    assert!(best.cost() < DEFAULT_SEPARATION_PENALTY);
+
+    assert_delta_consistent(&problem, 23);
+}
+
+#[test]
+fn unsatisfiable_separation_is_reported() {
+    let seats = vec![Seat { x: 0, y: 0 }, Seat { x: 1, y: 0 }];
+    let problem = Problem::new(seats, vec![vec![], vec![]], vec![vec![], vec![]])
+        .with_separations([(0, 1, Separation::DifferentRow)])
+        .unwrap()
+        .with_separation_penalty(50.0)
+        .unwrap();
+
+    let mut ilsa = ILSA::new(&problem, 2);
+    assert_eq!(
+        ilsa.solve(10).err(),
+        Some(SolveError::Infeasible { violations: 1 })
+    );
+    assert_eq!(
+        ParallelILSA::new(&problem, 2).solve(10).err(),
+        Some(SolveError::Infeasible { violations: 1 })
+    );
+    assert_eq!(
+        ExactSolver::new(&problem).solve().err(),
+        Some(SolveError::Infeasible { violations: 1 })
+    );
+
+    // 違反している配置そのものは評価できる
+    let ind = Individual::from_by_seat(&problem, vec![0, 1]).unwrap();
+    assert!(!ind.is_feasible());
+    assert_eq!(ind.violations(), 1);
+    assert!((ind.cost() - 50.0).abs() < 1e-6);
+}
+
+/// 学生 0 と 1 は強く隣を希望するが、2 席以上離す必要がある（距離 2 でコスト 10000 の配置が存在する）
+fn strong_pair_separation_problem() -> Problem {
+    let seats = (0..4).map(|x| Seat { x, y: 0 }).collect();
+    Problem::new(
+        seats,
+        vec![vec![]; 4],
+        vec![vec![(1, 5000.0)], vec![(0, 5000.0)], vec![], vec![]],
+    )
+    .with_separations([(0, 1, Separation::MinDistance(2))])
+    .unwrap()
+}
+
+#[test]
+fn separation_is_kept_when_pair_weight_exceeds_penalty() {
+    let problem = strong_pair_separation_problem();
+
+    for seed in 1..=3 {
+        let best = ILSA::new(&problem, seed).solve(20).unwrap();
+        assert!(best.is_feasible());
+        assert!((best.cost() - 10000.0).abs() < 1e-3);
+    }
+    let best = ParallelILSA::new(&problem, 2).solve(20).unwrap();
+    assert!(best.is_feasible());
+    assert!((best.cost() - 10000.0).abs() < 1e-3);
+}
+
+#[test]
+fn exact_solver_prunes_separation_violations() {
+    let problem = strong_pair_separation_problem();
+    let exact = ExactSolver::new(&problem).solve().unwrap();
+    assert!(exact.proven_optimal);
+    assert!(exact.best.is_feasible());
+    assert!((exact.best.cost() - 10000.0).abs() < 1e-3);
+}
+
+#[test]
+fn try_new_reports_invalid_input() {
+    let seats = || vec![Seat { x: 0, y: 0 }, Seat { x: 1, y: 0 }];
+
+    assert_eq!(
+        Problem::try_new(seats(), vec![vec![], vec![(2, 1.0)]], vec![vec![], vec![]]).err(),
+        Some(ProblemError::WantSeatOutOfRange {
+            student: 1,
+            seat: 2
+        })
+    );
+    assert_eq!(
+        Problem::try_new(seats(), vec![vec![], vec![]], vec![vec![(5, 1.0)], vec![]]).err(),
+        Some(ProblemError::PairStudentOutOfRange {
+            student: 0,
+            other: 5
+        })
+    );
+    assert_eq!(
+        Problem::try_new(seats(), vec![vec![], vec![]], vec![vec![]]).err(),
+        Some(ProblemError::LengthMismatch {
+            want_seats: 2,
+            pair_edges: 1
+        })
+    );
+    assert_eq!(
+        Problem::try_new(
+            vec![Seat { x: 0, y: 0 }, Seat { x: 0, y: 0 }],
+            vec![vec![]],
+            vec![vec![]]
+        )
+        .err(),
+        Some(ProblemError::DuplicateSeat {
+            first: 0,
+            second: 1
+        })
+    );
+    assert_eq!(
+        Problem::try_new(seats(), vec![vec![(0, f32::NAN)]], vec![vec![]]).err(),
+        Some(ProblemError::NonFiniteWantWeight {
+            student: 0,
+            seat: 0
+        })
+    );
+    assert_eq!(
+        Problem::try_new(
+            seats(),
+            vec![vec![], vec![]],
+            vec![vec![(0, f32::INFINITY)], vec![]]
+        )
+        .err(),
+        Some(ProblemError::NonFinitePairWeight {
+            student: 0,
+            other: 0
+        })
+    );
+    assert_eq!(
+        Problem::try_new(seats(), vec![vec![]; 3], vec![vec![]; 3]).err(),
+        Some(ProblemError::StudentsExceedSeats {
+            students: 3,
+            seats: 2
+        })
+    );
+
+    let too_many = (0..=u16::MAX as i32)
+        .map(|i| Seat {
+            x: (i % 256) as i16,
+            y: (i / 256) as i16,
+        })
+        .collect();
+    assert_eq!(
+        Problem::try_new(too_many, vec![], vec![]).err(),
+        Some(ProblemError::TooManySeats {
+            count: u16::MAX as usize + 1
+        })
+    );
+}
+
+#[test]
+fn solve_rejects_unvalidated_problem() {
+    let problem = Problem::new(
+        vec![Seat { x: 0, y: 0 }],
+        vec![vec![(3, 1.0)]],
+        vec![vec![]],
+    );
+    let mut ilsa = ILSA::new(&problem, 1);
+    assert_eq!(
+        ilsa.solve(5).unwrap_err(),
+        SolveError::InvalidProblem(ProblemError::WantSeatOutOfRange {
+            student: 0,
+            seat: 3
+        })
+    );
+}
+
+#[test]
+fn builder_maps_keys_both_ways() {
+    let mut builder = ProblemBuilder::new();
+    for (i, label) in ["A-1", "A-2", "A-3", "B-1", "B-2", "B-3"]
+        .iter()
+        .enumerate()
+    {
+        builder.seat(
+            *label,
+            Seat {
+                x: (i % 3) as i16,
+                y: (i / 3) as i16,
+            },
+        );
+    }
+    builder
+        .student("aoi")
+        .student("ren")
+        .student("mio")
+        .student("kai");
+    builder
+        .want_seat("aoi", "B-3", 1.0)
+        .pair("ren", "mio", 1.0)
+        .pin("kai", "A-1")
+        .forbid("ren", "A-2")
+        .separate("aoi", "kai", Separation::MinDistance(2));
+
+    let (problem, ids) = builder.build().unwrap();
+    assert_eq!(problem.student_count(), 4);
+    assert_eq!(problem.seat_count(), 6);
+    assert_eq!(ids.student_id("mio"), Some(2));
+    assert_eq!(ids.seat_key(4), Some("B-2"));
+    assert_eq!(problem.pinned_seats(), [(3, 0)]);
+    assert!(problem.is_forbidden(1, 1));
+
+    let best = ILSA::new(&problem, 5).solve(20).unwrap();
+    let seating = ids.assignments(best.by_seat());
+    assert_eq!(seating.len(), 4);
+    assert!(seating.contains(&("aoi", "B-3")));
+    assert!(seating.contains(&("kai", "A-1")));
+    assert!(!seating.contains(&("ren", "A-2")));
+}
+
+#[test]
+fn builder_rejects_unknown_and_duplicate_keys() {
+    let mut builder = ProblemBuilder::new();
+    builder.seat("A-1", Seat { x: 0, y: 0 }).student("aoi");
+    builder.want_seat("aoi", "Z-9", 1.0);
+    assert_eq!(
+        builder.build().err(),
+        Some(ProblemError::UnknownSeatKey {
+            key: "Z-9".to_owned()
+        })
+    );
+
+    let mut builder = ProblemBuilder::new();
+    builder
+        .seat("A-1", Seat { x: 0, y: 0 })
+        .student("aoi")
+        .student("aoi");
+    assert_eq!(
+        builder.build().err(),
+        Some(ProblemError::DuplicateStudentKey {
+            key: "aoi".to_owned()
+        })
+    );
+}
+
+fn asymmetric_problem() -> Problem {
+    let seats = (0..6).map(|i| Seat { x: i % 3, y: i / 3 }).collect();
+    let want_seats = vec![vec![]; 5];
+    // 0 <-> 1 は重みが食い違い、2 -> 1 と 4 -> 3 は片方向のみ、0 -> 0 は自己辺
+    let pair_edges = vec![
+        vec![(1, 1.0), (0, 3.0)],
+        vec![(0, 0.2)],
+        vec![(1, 0.6)],
+        vec![],
+        vec![(3, 0.9), (3, 0.3)],
+    ];
+    Problem::new(seats, want_seats, pair_edges)
+}
+
+#[test]
+fn pair_edges_are_symmetrized_with_policy() {
+    let expected = |policy, w01: f32, w34: f32| {
+        let problem = asymmetric_problem().with_pair_merge_policy(policy);
+        let graph = problem.pair_graph();
+        assert_eq!(graph[0], [(1, w01)]);
+        assert_eq!(graph[1], [(0, w01), (2, 0.6)]);
+        assert_eq!(graph[2], [(1, 0.6)]);
+        assert_eq!(graph[3], [(4, w34)]);
+        assert_eq!(graph[4], [(3, w34)]);
+        assert_delta_consistent(&problem, 31);
+    };
+    expected(PairMergePolicy::Sum, 1.2, 1.2);
+    expected(PairMergePolicy::Average, 0.6, 0.6);
+    expected(PairMergePolicy::Min, 0.2, 0.3);
+    expected(PairMergePolicy::Max, 1.0, 0.9);
+
+    let problem = asymmetric_problem();
+    assert_eq!(problem.pair_merge_policy(), PairMergePolicy::Average);
+    assert_eq!(problem.pair_edges()[2], [(1, 0.6)]);
+}
+
+#[test]
+fn distance_table_matches_distance_fn() {
+    let problem = vacant_problem();
+    assert_eq!(problem.distance_table.len(), 36);
+    for (a, sa) in problem.seats().iter().enumerate() {
+        for (b, sb) in problem.seats().iter().enumerate() {
+            assert_eq!(
+                problem.distance(a as u16, b as u16),
+                problem.distance_fn().distance((sa.x, sa.y), (sb.x, sb.y))
+            );
+        }
+    }
+}
+
+#[test]
+fn preference_table_is_optional_and_equivalent() {
+    let seats = (0..9)
+        .map(|i| Seat { x: i % 3, y: i / 3 })
+        .collect::<Vec<_>>();
+    let want_seats = (0..8u16)
+        .map(|s| vec![((s * 5) % 9, 1.0), ((s + 4) % 9, 0.6), (s % 9, -0.3)])
+        .collect::<Vec<_>>();
+    let pair_edges = vec![
+        vec![(1, 0.5)],
+        vec![],
+        vec![(3, 0.4)],
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+    ];
+
+    let with_table = Problem::new(seats.clone(), want_seats.clone(), pair_edges.clone());
+    let without_table = Problem::new(seats, want_seats, pair_edges).with_preference_table_limit(0);
+    assert_eq!(with_table.preference_table.len(), 8 * 9);
+    assert!(without_table.preference_table.is_empty());
+
+    for student in 0..8 {
+        for seat in 0..9 {
+            assert_eq!(
+                with_table.preference_cost(student, seat),
+                without_table.preference_cost(student, seat)
+            );
+        }
+    }
+    // 学生 0 が席 8 に座ると、嫌いな席 0 からの距離 4 × -0.3 が最小
+    assert!((with_table.preference_cost(0, 8) + 1.2).abs() < 1e-6);
+
+    let best_a = ILSA::new(&with_table, 77).solve(30).unwrap();
+    let best_b = ILSA::new(&without_table, 77).solve(30).unwrap();
+    assert_eq!(best_a.by_seat(), best_b.by_seat());
+    assert_eq!(best_a.cost(), best_b.cost());
+    assert_delta_consistent(&with_table, 41);
+}
+
+fn scaled_classroom(seat_count: usize, weight: f32) -> Problem {
+    let seats = (0..seat_count)
+        .map(|i| Seat {
+            x: (i % 8) as i16,
+            y: (i / 8) as i16,
+        })
+        .collect();
+    let want_seats = (0..seat_count)
+        .map(|s| vec![(((s * 7) % seat_count) as u16, weight)])
+        .collect();
+    let pair_edges = (0..seat_count)
+        .map(|s| vec![(((s + 5) % seat_count) as u16, weight)])
+        .collect();
+    Problem::new(seats, want_seats, pair_edges)
+}
+
+#[test]
+fn auto_temperature_scales_with_cost() {
+    let config = ILSAConfig {
+        initial_temperature: InitialTemperature::Auto { acceptance: 0.5 },
+        ..ILSAConfig::default()
+    };
+    let small = scaled_classroom(40, 1.0);
+    let large = scaled_classroom(40, 100.0);
+
+    let mut rng = SimpleRng::new(3);
+    let start_small = Individual::new_random(&small, &mut rng).unwrap();
+    let mut rng = SimpleRng::new(3);
+    let start_large = Individual::new_random(&large, &mut rng).unwrap();
+
+    let t_small = ILSA::with_config(&small, 9, config.clone()).initial_temperature(&start_small);
+    let t_large = ILSA::with_config(&large, 9, config.clone()).initial_temperature(&start_large);
+    assert!(t_small > 0.0 && t_small.is_finite());
+    assert!((t_large / t_small - 100.0).abs() < 1e-2);
+
+    let fixed = ILSA::new(&small, 9).config().initial_temperature;
+    assert_eq!(fixed, InitialTemperature::Fixed(100.0));
+
+    let best = ILSA::with_config(&large, 9, config).solve(30).unwrap();
+    assert!(best.cost().is_finite());
+}
+
+#[test]
+fn cancelled_or_expired_search_returns_best_so_far() {
+    let problem = scaled_classroom(40, 1.0);
+
+    let flag = Arc::new(AtomicBool::new(true));
+    let start = Instant::now();
+    let mut ilsa = ILSA::new(&problem, 5).with_cancel_flag(flag.clone());
+    let best = ilsa.solve(1_000_000).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(best.seat_of().len(), problem.student_count());
+    assert!(best.cost().is_finite());
+
+    let config = ILSAConfig {
+        time_limit: Some(Duration::from_millis(50)),
+        ..ILSAConfig::default()
+    };
+    let start = Instant::now();
+    let mut ilsa = ILSA::with_config(&problem, 5, config);
+    let candidates = ilsa.solve_candidates(1_000_000, 3).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(candidates.len(), 3);
+}
+
+#[test]
+fn observer_sees_progress_and_can_stop() {
+    let problem = scaled_classroom(24, 1.0);
+    let mut seen = Vec::new();
+    let best = ILSA::new(&problem, 13)
+        .with_observer(|progress: &Progress| {
+            seen.push(*progress);
+            if progress.iteration == 5 {
+                Control::Stop
+            } else {
+                Control::Continue
+            }
+        })
+        .solve(1_000)
+        .unwrap();
+
+    assert_eq!(seen.len(), 5);
+    assert_eq!(
+        seen.iter().map(|p| p.iteration).collect::<Vec<_>>(),
+        [1, 2, 3, 4, 5]
+    );
+    assert!(seen.windows(2).all(|w| w[1].temperature < w[0].temperature));
+    assert!(seen.windows(2).all(|w| w[1].best_cost <= w[0].best_cost));
+    assert!(seen.iter().all(|p| p.best_cost <= p.current_cost + 1e-6));
+    assert!((best.cost() - seen[4].best_cost).abs() < 1e-3);
+}
+
+#[test]
+fn parallel_solver_is_deterministic_across_thread_counts() {
+    let problem = scaled_classroom(24, 1.0);
+
+    let single = ParallelILSA::new(&problem, 2024)
+        .with_chains(6)
+        .with_threads(1)
+        .solve_candidates(20, 3)
+        .unwrap();
+    let multi = ParallelILSA::new(&problem, 2024)
+        .with_chains(6)
+        .with_threads(4)
+        .solve_candidates(20, 3)
+        .unwrap();
+
+    assert_eq!(single.len(), 3);
+    for (a, b) in single.iter().zip(&multi) {
+        assert_eq!(a.by_seat(), b.by_seat());
+        assert_eq!(a.cost(), b.cost());
+    }
+}
+
+/// 希望席だけの問題（ペアなし）を乱数で作る
+fn preference_only_problem(seed: u64) -> Problem {
+    let mut rng = SimpleRng::new(seed);
+    let seats: Vec<Seat> = (0..7).map(|i| Seat { x: i % 4, y: i / 4 }).collect();
+    let want_seats = (0..6)
+        .map(|_| {
+            (0..2)
+                .map(|_| (rng.gen_range(0..7) as u16, 0.5 + rng.next_f32()))
+                .collect()
+        })
+        .collect();
+    Problem::new(seats, want_seats, vec![vec![]; 6])
+}
+
+/// 固定割り当てと禁止席を守る全割り当てを列挙した最小コスト
+fn brute_force_min_cost(problem: &Problem) -> f32 {
+    fn search(problem: &Problem, student: usize, used: &mut [bool], seat_of: &mut Vec<u16>) -> f32 {
+        if student == problem.student_count() {
+            return Individual::calc_cost(problem, seat_of);
+        }
+        let pinned = problem
+            .pinned_seats()
+            .iter()
+            .find(|&&(s, _)| s as usize == student)
+            .map(|&(_, seat)| seat);
+        let mut best = f32::INFINITY;
+        for seat in 0..problem.seat_count() {
+            let allowed = match pinned {
+                Some(pin) => seat == pin as usize,
+                None => !used[seat] && !problem.is_forbidden(student as u16, seat as u16),
+            };
+            if !allowed {
+                continue;
+            }
+            used[seat] = true;
+            seat_of.push(seat as u16);
+            best = best.min(search(problem, student + 1, used, seat_of));
+            seat_of.pop();
+            used[seat] = pinned.is_some();
+        }
+        best
+    }
+    let mut used = vec![false; problem.seat_count()];
+    for &(_, seat) in problem.pinned_seats() {
+        used[seat as usize] = true;
+    }
+    search(problem, 0, &mut used, &mut Vec::new())
+}
+
+#[test]
+fn assignment_solver_is_optimal_for_preference_only_problems() {
+    for seed in 1..=5 {
+        let problem = preference_only_problem(seed)
+            .with_pinned_seats([(0, 3)])
+            .unwrap()
+            .with_forbidden_seats([(1, 0), (1, 1), (2, 6)])
+            .unwrap();
+        let exact = AssignmentSolver::new(&problem).solve().unwrap();
+        let expected = brute_force_min_cost(&problem);
+        assert!((exact.cost() - expected).abs() < 1e-4, "seed={}", seed);
+        assert_eq!(exact.seat_of()[0], 3);
+        assert!(![0, 1].contains(&exact.seat_of()[1]));
+        assert_ne!(exact.seat_of()[2], 6);
+    }
+}
+
+#[test]
+fn assignment_solver_rejects_pair_terms_and_warm_starts_ilsa() {
+    let err = AssignmentSolver::new(&sample_problem())
+        .solve()
+        .unwrap_err();
+    assert_eq!(err, SolveError::NotAssignmentProblem);
+
+    let problem = preference_only_problem(9);
+    let exact = AssignmentSolver::new(&problem).solve().unwrap();
+    let config = ILSAConfig {
+        assignment_warm_start: true,
+        ..ILSAConfig::default()
+    };
+    let best = ILSA::with_config(&problem, 5, config).solve(0).unwrap();
+    assert!((best.cost() - exact.cost()).abs() < 1e-4);
+}
+
+/// README の例と同じ 3x3 教室（希望席なし、ペアのみ）
+fn readme_problem() -> Problem {
+    readme_problem_with(DefaultDistanceFn)
+}
+
+fn readme_problem_with<D: DistanceFn>(distance_fn: D) -> Problem<D> {
+    let seats = (0..9).map(|i| Seat { x: i % 3, y: i / 3 }).collect();
+    let want_seats = (0..9).map(|_| vec![]).collect();
+    let pair_edges = vec![
+        vec![(1, 0.8), (3, 0.7), (4, 1.0)],
+        vec![(2, 0.8), (4, 0.9)],
+        vec![(5, 0.7), (4, 0.9)],
+        vec![(6, 0.8), (4, 0.9)],
+        vec![(5, 0.9), (7, 0.9), (8, 0.8)],
+        vec![(8, 0.8)],
+        vec![(7, 0.8)],
+        vec![(8, 0.8)],
+        vec![],
+    ];
+    Problem::with_distance_fn(seats, want_seats, pair_edges, distance_fn)
+}
+
+#[test]
+fn exact_solver_matches_brute_force_and_ilsa() {
+    let problem = vacant_problem()
+        .with_pinned_seats([(1, 0)])
+        .unwrap()
+        .with_forbidden_seats([(2, 4)])
+        .unwrap()
+        .with_separations([(0, 3, Separation::DifferentRow)])
+        .unwrap();
+    let exact = ExactSolver::new(&problem).solve().unwrap();
+    assert!(exact.proven_optimal);
+    assert!((exact.best.cost() - brute_force_min_cost(&problem)).abs() < 1e-4);
+
+    // README の 3x3 教室では ILSA も最適解に到達する
+    let readme = readme_problem();
+    let exact = ExactSolver::new(&readme).solve().unwrap();
+    assert!(exact.proven_optimal);
+    let best = ILSA::new(&readme, 42).solve(9).unwrap();
+    assert!((best.cost() - exact.best.cost()).abs() < 1e-4);
+}
+
+#[test]
+fn exact_solver_reports_limits() {
+    let readme = readme_problem();
+    let limited = ExactSolver::new(&readme)
+        .with_node_limit(3)
+        .solve()
+        .unwrap();
+    assert!(!limited.proven_optimal);
+    assert!(limited.best.is_feasible());
+
+    let err = ExactSolver::new(&scaled_classroom(20, 1.0))
+        .solve()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SolveError::TooLargeForExact {
+            seats: 20,
+            limit: EXACT_MAX_SEATS
+        }
+    );
+}
+
+#[test]
+fn lower_bound_is_valid_and_tight_without_pairs() {
+    // 希望席だけなら下界は割当問題の最適値そのもの
+    let problem = preference_only_problem(3);
+    let exact = AssignmentSolver::new(&problem).solve().unwrap();
+    assert!((problem.lower_bound().unwrap() - exact.cost()).abs() < 1e-4);
+
+    // ペアありでも最適値を超えない
+    for problem in [readme_problem(), vacant_problem(), grid_problem()] {
+        let optimum = ExactSolver::new(&problem).solve().unwrap().best.cost();
+        assert!(problem.lower_bound().unwrap() <= optimum + 1e-4);
+    }
+}
+
+#[test]
+fn solve_with_gap_reports_bound_and_gap() {
+    let problem = readme_problem();
+    let (best, report) = ILSA::new(&problem, 42).solve_with_gap(9).unwrap();
+    let report = report.unwrap();
+    assert_eq!(report.cost, best.cost());
+    assert_eq!(report.lower_bound, problem.lower_bound().unwrap());
+    assert!(report.absolute_gap >= 0.0);
+    assert!((report.cost - report.absolute_gap - report.lower_bound).abs() < 1e-4);
+    assert!((report.relative_gap - report.absolute_gap / best.cost()).abs() < 1e-6);
+
+    assert_eq!(GapReport::new(0.0, 0.0).relative_gap, 0.0);
+    assert_eq!(GapReport::new(3.0, 5.0).absolute_gap, 0.0);
+}
+
+#[test]
+fn solve_with_gap_keeps_solution_without_bound() {
+    let lex = readme_problem()
+        .with_objective(Objective::LexMinMax { depth: 2 })
+        .unwrap();
+    let custom = readme_problem()
+        .with_cost_term(1.0, FrontRowTerm { student: 8 })
+        .unwrap();
+    for problem in [lex, custom] {
+        let (best, report) = ILSA::new(&problem, 42).solve_with_gap(9).unwrap();
+        assert!(report.is_none());
+        assert_eq!(best.seat_of().len(), problem.student_count());
+    }
+
+    // 問題自体が解けない場合は探索前にエラーを返す
+    let problem = sample_problem()
+        .with_forbidden_seats([(0, 0), (0, 1), (0, 2), (0, 3)])
+        .unwrap();
+    assert!(matches!(
+        ILSA::new(&problem, 1).solve_with_gap(5).err(),
+        Some(SolveError::NoFeasibleAssignment { .. })
+    ));
+}
+
+#[test]
+fn individual_from_by_seat_validates_assignment() {
+    let problem = vacant_problem().with_pinned_seats([(0, 5)]).unwrap();
+    let ind = Individual::from_by_seat(&problem, vec![1, EMPTY_SEAT, 2, 3, EMPTY_SEAT, 0]).unwrap();
+    assert_eq!(ind.seat_of(), &[5, 0, 2, 3]);
+    assert_eq!(ind.cost(), Individual::calc_cost(&problem, ind.seat_of()));
+
+    let cases = [
+        (
+            vec![1, 2, 3, 0],
+            SolveError::AssignmentLengthMismatch { seats: 6, len: 4 },
+        ),
+        (
+            vec![1, 9, 2, 3, EMPTY_SEAT, 0],
+            SolveError::AssignedStudentOutOfRange {
+                seat: 1,
+                student: 9,
+            },
+        ),
+        (
+            vec![1, 1, 2, 3, EMPTY_SEAT, 0],
+            SolveError::StudentAssignedTwice { student: 1 },
+        ),
+        (
+            vec![1, EMPTY_SEAT, 2, EMPTY_SEAT, EMPTY_SEAT, 0],
+            SolveError::StudentNotAssigned { student: 3 },
+        ),
+        (
+            vec![0, EMPTY_SEAT, 2, 3, EMPTY_SEAT, 1],
+            SolveError::PinnedSeatMismatch {
+                student: 0,
+                seat: 5,
+            },
+        ),
+    ];
+    for (by_seat, expected) in cases {
+        assert_eq!(
+            Individual::from_by_seat(&problem, by_seat).unwrap_err(),
+            expected
+        );
+    }
+
+    let forbidden = problem.with_forbidden_seats([(2, 2)]).unwrap();
+    assert_eq!(
+        Individual::from_by_seat(&forbidden, vec![1, EMPTY_SEAT, 2, 3, EMPTY_SEAT, 0]).unwrap_err(),
+        SolveError::AssignedToForbiddenSeat {
+            student: 2,
+            seat: 2
+        }
+    );
+}
+
+#[test]
+fn solve_from_starts_at_given_assignment() {
+    let problem = readme_problem();
+    let best = ILSA::new(&problem, 42).solve(9).unwrap();
+
+    // 局所最適から budget 0 で始めると、その配置がそのまま返る
+    let again = ILSA::new(&problem, 1).solve_from(&best, 0).unwrap();
+    assert_eq!(again.by_seat(), best.by_seat());
+    assert_eq!(again.cost(), best.cost());
+
+    // 悪い初期解からでも改善される
+    let worst = Individual::from_by_seat(&problem, vec![8, 6, 0, 2, 7, 3, 5, 1, 4]).unwrap();
+    let improved = ILSA::new(&problem, 1).solve_from(&worst, 20).unwrap();
+    assert!(improved.cost() < worst.cost());
+}
+
+#[test]
+fn stability_term_limits_movement() {
+    // 先週の配置（学生 0〜7）に、転入生 8 が加わる
+    let last_week = ILSA::new(&readme_problem(), 42).solve(9).unwrap();
+    let reference: Vec<(u16, u16)> = last_week
+        .seat_of()
+        .iter()
+        .enumerate()
+        .filter(|&(student, _)| student != 8)
+        .map(|(student, &seat)| (student as u16, seat))
+        .collect();
+
+    let weighted = readme_problem()
+        .with_stability(reference.clone(), 0.5, None)
+        .unwrap();
+    for seed in 1..=3 {
+        assert_delta_consistent(&weighted, seed);
+    }
+    let capped = readme_problem()
+        .with_stability(reference.clone(), 0.0, Some(2))
+        .unwrap()
+        .with_separations([(0, 4, Separation::MinDistance(2))])
+        .unwrap()
+        .with_separation_penalty(50.0)
+        .unwrap();
+    for seed in 1..=3 {
+        assert_delta_consistent(&capped, seed);
+    }
+    let best = ILSA::new(&capped, 5).solve(40).unwrap();
+    assert!(best.is_feasible());
+    assert!(best.moves() <= 2);
+    assert_eq!(
+        best.moves(),
+        Individual::count_moves(&capped, best.seat_of())
+    );
+
+    // 分枝限定法も移動人数の上限込みで最適解を返す
+    let exact = ExactSolver::new(&capped).solve().unwrap();
+    assert!((exact.best.cost() - brute_force_min_cost(&capped)).abs() < 1e-4);
+    assert!(exact.best.moves() <= 2);
+
+    assert_eq!(
+        readme_problem()
+            .with_stability([(0, 1), (0, 2)], 1.0, None)
+            .err(),
+        Some(ProblemError::StudentReferencedTwice { student: 0 })
+    );
+    assert_eq!(
+        readme_problem().with_stability(reference, -1.0, None).err(),
+        Some(ProblemError::InvalidStabilityWeight)
+    );
+}
+
+#[test]
+fn unsatisfiable_move_limit_is_reported() {
+    // 学生 0 は基準の座席 0 から座席 1 に固定されているので、移動 0 人は守れない
+    let seats = vec![Seat { x: 0, y: 0 }, Seat { x: 1, y: 0 }];
+    let problem = Problem::new(seats, vec![vec![], vec![]], vec![vec![], vec![]])
+        .with_pinned_seats([(0, 1)])
+        .unwrap()
+        .with_stability([(0, 0)], 0.0, Some(0))
+        .unwrap();
+
+    assert_eq!(
+        ILSA::new(&problem, 3).solve(10).err(),
+        Some(SolveError::Infeasible { violations: 1 })
+    );
+    assert_eq!(
+        ExactSolver::new(&problem).solve().err(),
+        Some(SolveError::Infeasible { violations: 1 })
+    );
+}
+
+#[test]
+fn move_limit_is_kept_when_preferences_exceed_penalty() {
+    // 学生 0, 1 は席 0, 1 から動けないが、2 人とも席 3 を強く希望している
+    let seats = (0..4).map(|x| Seat { x, y: 0 }).collect();
+    let problem = Problem::new(
+        seats,
+        vec![vec![(3, 800.0)], vec![(3, 800.0)]],
+        vec![vec![], vec![]],
+    )
+    .with_stability([(0, 0), (1, 1)], 0.0, Some(0))
+    .unwrap();
+
+    let best = ILSA::new(&problem, 4).solve(20).unwrap();
+    assert_eq!(best.moves(), 0);
+    assert!((best.cost() - 4000.0).abs() < 1e-3);
+    let exact = ExactSolver::new(&problem).solve().unwrap();
+    assert_eq!(exact.best.moves(), 0);
+    assert!((exact.best.cost() - 4000.0).abs() < 1e-3);
+}
+
+#[test]
+fn history_penalizes_repeated_seats_and_neighbours() {
+    let last = ILSA::new(&readme_problem(), 42).solve(9).unwrap();
+    let older: Vec<u16> = (0..9).collect();
+    let penalty = HistoryPenalty {
+        same_seat: 3.0,
+        same_neighbour: 0.5,
+        decay: 0.5,
+        neighbour_distance: 1,
+    };
+    let problem = readme_problem()
+        .with_history([older.clone(), last.by_seat().to_vec()], penalty)
+        .unwrap();
+    for seed in 1..=3 {
+        assert_delta_consistent(&problem, seed);
+    }
+
+    // 直近の座席は 3.0、2 期前の座席は 1.5 のペナルティ
+    assert_eq!(problem.history_seat_cost(0, last.seat_of()[0]), 3.0);
+    assert_ne!(last.seat_of()[0], 0);
+    assert_eq!(problem.history_seat_cost(0, 0), 1.5);
+    let same = Individual::from_by_seat(&problem, last.by_seat().to_vec()).unwrap();
+    assert!(same.cost() > last.cost() + 9.0 * 3.0);
+
+    let best = ILSA::new(&problem, 42).solve(30).unwrap();
+    assert!(best.cost() < same.cost());
+    let exact = ExactSolver::new(&problem).solve().unwrap();
+    assert!(exact.proven_optimal);
+    assert!((exact.best.cost() - brute_force_min_cost(&problem)).abs() < 1e-3);
+
+    assert_eq!(
+        readme_problem()
+            .with_history([older.clone(), vec![0, 0]], penalty)
+            .err(),
+        Some(ProblemError::HistoryLengthMismatch {
+            period: 1,
+            seats: 9,
+            len: 2
+        })
+    );
+    let mut twice = older;
+    twice[1] = 0;
+    assert_eq!(
+        readme_problem().with_history([twice], penalty).err(),
+        Some(ProblemError::StudentTwiceInHistory {
+            period: 0,
+            student: 0
+        })
+    );
+    let bad = HistoryPenalty {
+        decay: 1.5,
+        ..penalty
+    };
+    assert_eq!(
+        readme_problem().with_history([], bad).err(),
+        Some(ProblemError::InvalidHistoryPenalty)
+    );
+}
+
+#[test]
+fn rotation_planner_spreads_neighbours_across_periods() {
+    let problem = readme_problem();
+    let plan = RotationPlanner::new(&problem, 42).plan(4, 30).unwrap();
+    assert_eq!(plan.periods.len(), 4);
+    for period in &plan.periods {
+        let mut students = period.by_seat().to_vec();
+        students.sort_unstable();
+        assert_eq!(students, (0..9).collect::<Vec<u16>>());
+        assert_eq!(
+            period.cost(),
+            Individual::calc_cost(&problem, period.seat_of())
+        );
+    }
+    assert_eq!(
+        plan.coverage,
+        NeighbourCoverage::compute(&problem, &plan.periods, 1)
+    );
+
+    // 隣の繰り返しにペナルティがなければ、毎期ほぼ同じ配置になる
+    let greedy = RotationPlanner::new(&problem, 42)
+        .with_penalty(HistoryPenalty {
+            same_seat: 0.0,
+            same_neighbour: 0.0,
+            ..HistoryPenalty::default()
+        })
+        .plan(4, 30)
+        .unwrap();
+    assert!(plan.coverage.repeated_pairs < greedy.coverage.repeated_pairs);
+    assert!(plan.coverage.mean_distinct > greedy.coverage.mean_distinct);
+}
+
+#[test]
+fn neighbour_coverage_counts_distinct_and_repeated_pairs() {
+    // 1 列 3 席: 0-1, 1-2 が隣
+    let seats = (0..3).map(|x| Seat { x, y: 0 }).collect();
+    let problem = Problem::new(seats, vec![vec![]; 3], vec![vec![]; 3]);
+    let periods = [vec![0, 1, 2], vec![1, 0, 2], vec![0, 1, 2]]
+        .map(|by_seat| Individual::from_by_seat(&problem, by_seat).unwrap());
+    let coverage = NeighbourCoverage::compute(&problem, &periods, 1);
+    assert_eq!(coverage.distinct_neighbours, vec![2, 2, 2]);
+    assert_eq!(coverage.min_distinct, 2);
+    // 2 期目の 1-0、3 期目の 0-1 と 1-2
+    assert_eq!(coverage.repeated_pairs, 3);
+}
+
+#[test]
+fn cost_breakdown_sums_exactly_to_cost() {
+    let problem = vacant_problem()
+        .with_separations([(0, 1, Separation::DifferentRow)])
+        .unwrap()
+        .with_separation_penalty(7.0)
+        .unwrap()
+        .with_stability([(0, 5), (1, 0), (2, 1)], 0.25, Some(1))
+        .unwrap()
+        .with_history(
+            [vec![0, 1, 2, 3, EMPTY_SEAT, EMPTY_SEAT]],
+            HistoryPenalty::default(),
+        )
+        .unwrap();
+    for seed in 1..=5 {
+        let best = ILSA::new(&problem, seed).solve(20).unwrap();
+        let breakdown = best.breakdown(&problem);
+        assert_eq!(breakdown.total(), best.cost());
+        assert_eq!(breakdown.objective, best.cost());
+
+        let mut sum = 0.0f32;
+        for student in &breakdown.students {
+            sum += student.total;
+            let parts = student.cost_terms
+                + student.history_seat
+                + student.movement
+                + student.history_neighbours
+                + student.separation;
+            assert!((parts - student.total).abs() < 1e-5);
+            // 既定のコスト項は重み 1 の希望席とペア距離
+            let builtin = student.preference + student.pairs.iter().map(|p| p.share).sum::<f32>();
+            assert!((builtin - student.cost_terms).abs() < 1e-5);
+        }
+        assert_eq!(sum + breakdown.move_limit_penalty, best.cost());
+    }
+}
+
+#[test]
+fn cost_breakdown_explains_wants_and_pairs() {
+    let problem = vacant_problem();
+    // 学生 0 は席 5 希望、席 1 に座らせる
+    let ind = Individual::from_by_seat(&problem, vec![1, 0, 2, 3, EMPTY_SEAT, EMPTY_SEAT]).unwrap();
+    let breakdown = ind.breakdown(&problem);
+    let s0 = &breakdown.students[0];
+    assert_eq!(s0.seat, 1);
+    let want = s0.best_want.unwrap();
+    assert_eq!((want.seat, want.distance, want.cost), (5, 2, 2.0));
+    assert_eq!(s0.preference, 2.0);
+    // 学生 0 と 2（席 2）は距離 1
+    assert_eq!(s0.pairs.len(), 1);
+    assert_eq!(s0.pairs[0].other, 2);
+    assert_eq!(s0.pairs[0].distance, 1);
+    assert_eq!(s0.pairs[0].share * 2.0, s0.pairs[0].cost);
+    // 希望席のない学生
+    assert!(breakdown.students[2].best_want.is_none());
+}
+
+#[test]
+fn metrics_report_satisfaction_and_fairness() {
+    // 1 列 4 席。学生 3 だけ第一希望（席 0）に座れない
+    let seats = (0..4).map(|x| Seat { x, y: 0 }).collect();
+    let want_seats = vec![
+        vec![(0, 1.0)],
+        vec![(1, 1.0), (3, 0.5)],
+        vec![(2, 1.0)],
+        vec![(3, 0.2), (0, 1.0)],
+    ];
+    let problem = Problem::new(seats, want_seats, vec![vec![]; 4]);
+    let ind = Individual::from_by_seat(&problem, vec![0, 1, 2, 3]).unwrap();
+    let metrics = Metrics::compute(&problem, &ind, 1);
+
+    assert_eq!(metrics.student_costs, vec![0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(metrics.first_choice_rate, Some(0.75));
+    assert_eq!(metrics.partner_rate, None);
+    assert_eq!(metrics.gini, None);
+
+    let ind = Individual::from_by_seat(&problem, vec![3, 1, 2, 0]).unwrap();
+    let metrics = Metrics::compute(&problem, &ind, 1);
+    // 学生 0 は席 3（距離 3）、学生 3 は席 0（第一希望）
+    assert_eq!(metrics.student_costs, vec![3.0, 0.0, 0.0, 0.0]);
+    assert_eq!(metrics.first_choice_rate, Some(0.75));
+    assert_eq!(metrics.worst_student, Some(0));
+    assert_eq!(metrics.worst_cost, 3.0);
+    assert_eq!(metrics.mean_cost, 0.75);
+    assert!((metrics.variance - 1.6875).abs() < 1e-6);
+    assert!((metrics.gini.unwrap() - 0.75).abs() < 1e-6);
+
+    // ペア: 学生 0 -> 1（隣）、学生 2 -> 0（距離 3）。統合後は 1 と 2 も相手を持つ
+    let seats = (0..4).map(|x| Seat { x, y: 0 }).collect();
+    let pair_edges = vec![vec![(1, 1.0)], vec![], vec![(0, 1.0)], vec![(2, -1.0)]];
+    let problem = Problem::new(seats, vec![vec![]; 4], pair_edges);
+    let ind = Individual::from_by_seat(&problem, vec![0, 1, 3, 2]).unwrap();
+    let metrics = Metrics::compute(&problem, &ind, 1);
+    assert!((metrics.partner_rate.unwrap() - 2.0 / 3.0).abs() < 1e-6);
+    assert_eq!(Metrics::compute(&problem, &ind, 3).partner_rate, Some(1.0));
+    let breakdown = ind.breakdown(&problem);
+    let totals: Vec<f32> = breakdown.students.iter().map(|s| s.total).collect();
+    assert_eq!(metrics.student_costs, totals);
+}
+
+#[test]
+fn lex_min_max_compares_top_costs_lexicographically() {
+    // 席 2, 3 は誰も座れない遠くの席。配置 A（学生 0 が席 0）の上位コストは [10.0, 9.0]、
+    // 配置 B（学生 0 が席 1）は [10.005, 0.0] で、最大値が小さい A が良い
+    let seats = vec![
+        Seat { x: 0, y: 0 },
+        Seat { x: 1, y: 0 },
+        Seat { x: 0, y: 5 },
+        Seat { x: 1, y: 10 },
+    ];
+    let want_seats = vec![vec![(2, 2.0), (3, 1.0005)], vec![(0, 9.0)]];
+    let problem = Problem::new(seats, want_seats, vec![vec![], vec![]])
+        .with_forbidden_seats([(0, 2), (0, 3), (1, 2), (1, 3)])
+        .unwrap()
+        .with_objective(Objective::LexMinMax { depth: 2 })
+        .unwrap();
+    let a = Individual::from_by_seat(&problem, vec![0, 1, EMPTY_SEAT, EMPTY_SEAT]).unwrap();
+    let b = Individual::from_by_seat(&problem, vec![1, 0, EMPTY_SEAT, EMPTY_SEAT]).unwrap();
+    assert_eq!(a.cost(), 10.0);
+    assert_eq!(a.compare(&b), std::cmp::Ordering::Less);
+    assert!(a.improves_on(&b, 1e-6));
+    for seed in 1..=3 {
+        assert_eq!(
+            ILSA::new(&problem, seed).solve(10).unwrap().seat_of(),
+            &[0, 1]
+        );
+    }
+
+    // 最大値が等しい配置どうしは 2 番目以降で比べる。全配置の最良と一致すること
+    let problem = vacant_problem()
+        .with_objective(Objective::LexMinMax { depth: 4 })
+        .unwrap();
+    let mut best: Option<Individual> = None;
+    let mut by_seat = vec![EMPTY_SEAT; problem.seat_count()];
+    fn visit(problem: &Problem, student: u16, by_seat: &mut [u16], best: &mut Option<Individual>) {
+        if student as usize == problem.student_count() {
+            let ind = Individual::from_by_seat(problem, by_seat.to_vec()).unwrap();
+            if best.as_ref().is_none_or(|b| ind.compare(b).is_lt()) {
+                *best = Some(ind);
+            }
+            return;
+        }
+        for seat in 0..by_seat.len() {
+            if by_seat[seat] == EMPTY_SEAT {
+                by_seat[seat] = student;
+                visit(problem, student + 1, by_seat, best);
+                by_seat[seat] = EMPTY_SEAT;
+            }
+        }
+    }
+    visit(&problem, 0, &mut by_seat, &mut best);
+    let best = best.unwrap();
+    assert!(!best.tail.is_empty());
+    let found = ILSA::new(&problem, 5).solve(30).unwrap();
+    assert!((found.cost() - best.cost()).abs() < 1e-4);
+    assert_eq!(found.tail, best.tail);
+}
+
+#[test]
+fn fairness_objectives_keep_incremental_cost_exact() {
+    let objectives = [
+        Objective::MinMax,
+        Objective::LexMinMax { depth: 3 },
+        Objective::Weighted {
+            sum_weight: 1.0,
+            max_weight: 2.0,
+        },
+    ];
+    for objective in objectives {
+        let problem = grid_problem()
+            .with_separations([(0, 2, Separation::DifferentRow)])
+            .unwrap()
+            .with_separation_penalty(50.0)
+            .unwrap()
+            .with_history(
+                [vec![0, 1, 2, 3, 4, 5, EMPTY_SEAT, EMPTY_SEAT, EMPTY_SEAT]],
+                HistoryPenalty::default(),
+            )
+            .unwrap()
+            .with_objective(objective)
+            .unwrap();
+        assert_delta_consistent(&problem, 31);
+        assert_delta_consistent(&vacant_problem().with_objective(objective).unwrap(), 7);
+    }
+
+    assert_eq!(
+        sample_problem()
+            .with_objective(Objective::LexMinMax { depth: 0 })
+            .err(),
+        Some(ProblemError::InvalidObjective)
+    );
+    let problem = sample_problem().with_objective(Objective::MinMax).unwrap();
+    assert_eq!(
+        ExactSolver::new(&problem).solve().err(),
+        Some(SolveError::UnsupportedObjective)
+    );
+    assert_eq!(
+        AssignmentSolver::new(&problem).solve().err(),
+        Some(SolveError::UnsupportedObjective)
+    );
+}
+
+#[test]
+fn min_max_objective_lowers_worst_student_cost() {
+    // 学生 0 と 2 が席 0 を取り合う。合計最小では学生 2 だけが遠くへ追いやられる
+    let seats = (0..4).map(|x| Seat { x, y: 0 }).collect();
+    let want_seats = vec![vec![(0, 1.0)], vec![(1, 1.0)], vec![(0, 0.9)]];
+    let problem = Problem::new(seats, want_seats, vec![vec![]; 3]);
+    let sum_best = AssignmentSolver::new(&problem).solve().unwrap();
+    assert!((sum_best.cost() - 1.8).abs() < 1e-6);
+    assert!((Metrics::compute(&problem, &sum_best, 1).worst_cost - 1.8).abs() < 1e-6);
+
+    let fair = problem.clone().with_objective(Objective::MinMax).unwrap();
+    let best = ILSA::new(&fair, 5).solve(20).unwrap();
+    assert!((best.cost() - 1.0).abs() < 1e-6);
+    let metrics = Metrics::compute(&fair, &best, 1);
+    assert!((metrics.worst_cost - 1.0).abs() < 1e-6);
+    // 内訳の合計は学生コストの合計、目的関数の値は `objective` に入る
+    let breakdown = best.breakdown(&fair);
+    assert!((breakdown.objective - best.cost()).abs() < 1e-6);
+    assert!((breakdown.total() - metrics.student_costs.iter().sum::<f32>()).abs() < 1e-6);
+    assert!(breakdown.total() > breakdown.objective);
+    assert!(fair.lower_bound().unwrap() <= best.cost());
+}
+
+/// 学生 `student` を前の列 (y = 0) に座らせたい、学校独自のコスト項（学生には帰属させない）
+struct FrontRowTerm {
+    student: u16,
+}
+
+impl CostTerm for FrontRowTerm {
+    fn evaluate(&self, problem: &Problem, seat_of: &[u16]) -> f32 {
+        problem.seats()[seat_of[self.student as usize] as usize].y as f32
+    }
+
+    fn delta_swap(&self, problem: &Problem, swap: &Swap<'_>) -> f32 {
+        let row = |seat: u16| problem.seats()[seat as usize].y as f32;
+        swap.moved()
+            .iter()
+            .filter(|m| m.student == self.student)
+            .map(|m| row(m.to) - row(m.from))
+            .sum()
+    }
+}
+
+#[test]
+fn builtin_cost_terms_match_core_cost() {
+    let problem = sample_problem();
+    let mut rng = SimpleRng::new(9);
+    for _ in 0..10 {
+        let ind = Individual::new_random(&problem, &mut rng).unwrap();
+        let terms = SeatPreferenceTerm.evaluate(&problem, ind.seat_of())
+            + PairDistanceTerm.evaluate(&problem, ind.seat_of());
+        assert!((terms - ind.cost()).abs() < 1e-5);
+    }
+
+    // 組み込みの項を重ねると、その分だけ重みが上乗せされる
+    let heavier = sample_problem()
+        .with_cost_term(1.0, PairDistanceTerm)
+        .unwrap()
+        .with_cost_term(0.5, SeatPreferenceTerm)
+        .unwrap();
+    let ind = Individual::from_by_seat(&heavier, vec![3, 2, 1, 0]).unwrap();
+    let base = Individual::from_by_seat(&problem, vec![3, 2, 1, 0]).unwrap();
+    let expected = base.cost()
+        + PairDistanceTerm.evaluate(&problem, base.seat_of())
+        + 0.5 * SeatPreferenceTerm.evaluate(&problem, base.seat_of());
+    assert!((ind.cost() - expected).abs() < 1e-5);
+    assert_eq!(ind.breakdown(&heavier).total(), ind.cost());
+    assert_delta_consistent(&heavier, 4);
+    assert!(heavier.lower_bound().unwrap() <= ind.cost());
+
+    // 組み込みの項を外して重みを変えると、その重みだけで評価される（重複して数えない）
+    let reweighted = sample_problem()
+        .clear_cost_terms()
+        .with_cost_term(2.0, PairDistanceTerm)
+        .unwrap();
+    let ind = Individual::from_by_seat(&reweighted, vec![3, 2, 1, 0]).unwrap();
+    let pairs = PairDistanceTerm.evaluate(&reweighted, ind.seat_of());
+    assert!((ind.cost() - 2.0 * pairs).abs() < 1e-5);
+    assert_eq!(ind.breakdown(&reweighted).total(), ind.cost());
+    assert_delta_consistent(&reweighted, 6);
+    assert!(reweighted.lower_bound().unwrap() <= ind.cost());
+    assert_eq!(
+        ExactSolver::new(&reweighted).solve().err(),
+        Some(SolveError::UnsupportedCostTerm)
+    );
+
+    assert_eq!(
+        sample_problem()
+            .with_cost_term(-1.0, PairDistanceTerm)
+            .err(),
+        Some(ProblemError::InvalidCostTermWeight)
+    );
+}
+
+#[test]
+fn custom_cost_term_steers_search() {
+    let problem = readme_problem()
+        .with_cost_term(10.0, FrontRowTerm { student: 8 })
+        .unwrap();
+    assert_delta_consistent(&problem, 12);
+
+    let best = ILSA::new(&problem, 3).solve(30).unwrap();
+    assert_eq!(problem.seats()[best.seat_of()[8] as usize].y, 0);
+    let breakdown = best.breakdown(&problem);
+    assert_eq!(breakdown.cost_terms, vec![0.0]);
+    assert_eq!(breakdown.total(), best.cost());
+
+    assert_eq!(
+        problem.lower_bound().err(),
+        Some(SolveError::UnsupportedCostTerm)
+    );
+    assert_eq!(
+        ExactSolver::new(&problem).solve().err(),
+        Some(SolveError::UnsupportedCostTerm)
+    );
+}
+
+#[test]
+fn alternative_distance_fns_measure_readme_classroom() {
+    // 席 0 = (0, 0), 1 = (1, 0), 2 = (2, 0), 4 = (1, 1), 5 = (2, 1), 8 = (2, 2)
+    let problem = readme_problem_with(ChebyshevDistanceFn);
+    assert_eq!(problem.distance(0, 4), 1);
+    assert_eq!(problem.distance(0, 5), 2);
+    assert_eq!(problem.distance(0, 8), 2);
+
+    let problem = readme_problem_with(EuclideanDistanceFn);
+    assert_eq!(problem.distance(0, 1), 1);
+    assert_eq!(problem.distance(0, 4), 1); // √2
+    assert_eq!(problem.distance(0, 5), 2); // √5
+    assert_eq!(problem.distance(0, 8), 3); // √8
+
+    let problem = readme_problem_with(DeskPairDistanceFn::<DefaultDistanceFn>::default());
+    assert_eq!(problem.distance(0, 1), 0);
+    assert_eq!(problem.distance(1, 2), 1);
+    assert_eq!(problem.distance(0, 3), 1);
+    assert_eq!(problem.distance(3, 4), 0);
+
+    let problem = readme_problem_with(CappedDistanceFn {
+        inner: ChebyshevDistanceFn,
+        threshold: 1,
+        cap: 2,
+    });
+    assert_eq!(problem.distance(0, 4), 0);
+    assert_eq!(problem.distance(0, 8), 2);
+    let problem = readme_problem_with(CappedDistanceFn {
+        inner: DefaultDistanceFn,
+        threshold: 0,
+        cap: 3,
+    });
+    assert_eq!(problem.distance(0, 8), 3);
+    assert_eq!(problem.distance(0, 4), 2);
+}
+
+#[test]
+fn readme_classroom_is_solved_under_each_distance_fn() {
+    fn check<D: DistanceFn>(problem: &Problem<D>) -> f32 {
+        let exact = ExactSolver::new(problem).solve().unwrap();
+        assert!(exact.proven_optimal);
+        let best = ILSA::new(problem, 11).solve(30).unwrap();
+        assert!((best.cost() - exact.best.cost()).abs() < 1e-4);
+        exact.best.cost()
+    }
+
+    // 学生 4 を中央に、残りを周囲の輪に並べると全ペアが斜め含みで隣になる
+    let chebyshev = check(&readme_problem_with(ChebyshevDistanceFn));
+    assert!((chebyshev - 12.5).abs() < 1e-4);
+    let capped = check(&readme_problem_with(CappedDistanceFn {
+        inner: ChebyshevDistanceFn,
+        threshold: 1,
+        cap: 2,
+    }));
+    assert_eq!(capped, 0.0);
+    let euclidean = check(&readme_problem_with(EuclideanDistanceFn));
+    assert!(euclidean >= chebyshev && euclidean <= 15.2 + 1e-4);
+    let desk_pair = check(&readme_problem_with(
+        DeskPairDistanceFn::<DefaultDistanceFn>::default(),
+    ));
+    assert!(desk_pair < 15.2);
+}
+
+#[test]
+fn public_fields_can_be_edited_then_refreshed() {
+    let mut problem = sample_problem();
+    problem.pair_edges[0] = vec![(3, 2.0)];
+    problem.want_seats[1] = vec![(2, 1.5)];
+    problem.seats[3] = Seat { x: 2, y: 2 };
+    problem.refresh();
+
+    let rebuilt = Problem::new(
+        problem.seats.clone(),
+        problem.want_seats.clone(),
+        problem.pair_edges.clone(),
+    );
+    assert_eq!(problem.pair_graph(), rebuilt.pair_graph());
+    assert_eq!(problem.distance(0, 3), 4);
+    for by_seat in [vec![0, 1, 2, 3], vec![3, 2, 1, 0], vec![1, 3, 0, 2]] {
+        let a = Individual::from_by_seat(&problem, by_seat.clone()).unwrap();
+        let b = Individual::from_by_seat(&rebuilt, by_seat).unwrap();
+        assert_eq!(a.cost(), b.cost());
+    }
+}
+
+#[test]
+fn time_limit_interrupts_a_long_hill_climb() {
+    // 600 席では 1 回のヒルクライムだけで制限時間を大きく超える
+    let problem = scaled_classroom(600, 1.0);
+    let config = ILSAConfig {
+        time_limit: Some(Duration::from_millis(20)),
+        ..ILSAConfig::default()
+    };
+    let start = Instant::now();
+    let best = ILSA::with_config(&problem, 5, config).solve(1_000).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!((best.cost() - Individual::calc_cost(&problem, best.seat_of())).abs() < 1e-3);
+}
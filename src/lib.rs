@@ -23,11 +23,22 @@
 //!
 //! let problem = Problem::new(seats, want_seats, pair_edges);
 //! let mut solver = ILSA::new(&problem, 0 /* 乱数シード 0 でシステムからgetrandom */);
-//! let best = solver.solve(1 /* 最大ジャンプ回数 座席数程度で安定 */);
+//! let best = solver
+//!     .solve(1 /* 最大ジャンプ回数 座席数程度で安定 */)
+//!     .expect("制約を満たす割り当てが存在する");
 //!
 //! assert_eq!(best.by_seat().len(), 1);
 //! ```
 
 pub mod engine;
 
-pub use engine::{ILSA, Individual, Problem, Seat, WeightedSeatPref, DistanceFn};
+pub use engine::{
+    AssignmentSolver, CappedDistanceFn, ChebyshevDistanceFn, Control, CostBreakdown, CostTerm,
+    DEFAULT_CHAINS, DEFAULT_EXACT_NODE_LIMIT, DEFAULT_PREFERENCE_TABLE_LIMIT,
+    DEFAULT_SEPARATION_PENALTY, DISTANCE_TABLE_MAX_SEATS, DefaultDistanceFn, DeskPairDistanceFn,
+    DistanceFn, EMPTY_SEAT, EXACT_MAX_SEATS, EuclideanDistanceFn, ExactSolution, ExactSolver,
+    GapReport, HistoryPenalty, ILSA, ILSAConfig, IdMap, Individual, InitialTemperature, Metrics,
+    NeighbourCoverage, Objective, Observer, PairDistanceTerm, PairMergePolicy, ParallelILSA,
+    Problem, ProblemBuilder, ProblemError, Progress, RotationPlan, RotationPlanner, Seat, SeatMove,
+    SeatPreferenceTerm, Separation, SolveError, Swap, WeightedSeatPref,
+};
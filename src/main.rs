@@ -1,4 +1,4 @@
-use sekigae3::{ILSA, Problem, Seat};
+use sekigae3::{ILSA, Problem, Seat, SolveError};
 
 fn build_simple_problem() -> Problem {
     let seats = vec![
@@ -28,7 +28,7 @@ fn build_simple_problem() -> Problem {
     // ]; // つまり逆順 ペア整合より重みつよいので優先
 
     // pair_edges[student] = [(other_student_id, weight)]
-    // a < b の辺だけを入れる簡易定義
+    // 片方向だけ入れれば、構築時に対称化される (PairMergePolicy で統合方法を指定可)
     let pair_edges = vec![
         vec![(1, 0.8), (3, 0.7), (4, 1.0)], // 学生0は学生1と3と4と仲が良い
         vec![(2, 0.8), (4, 0.9)],           // 学生1は学生2と4と仲が良い
@@ -44,14 +44,15 @@ fn build_simple_problem() -> Problem {
     Problem::new(seats, want_seats, pair_edges)
 }
 
-fn main() {
+fn main() -> Result<(), SolveError> {
     let problem = build_simple_problem();
 
     let seat_count = problem.seat_count();
 
     let mut ilsa = ILSA::new(&problem, 42);
-    let best = ilsa.solve(seat_count);
+    let best = ilsa.solve(seat_count)?;
 
     println!("best cost: {:.3}", best.cost());
     println!("seat -> student: {:?}", best.by_seat());
+    Ok(())
 }